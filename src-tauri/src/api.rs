@@ -0,0 +1,163 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::graph::centrality::Centrality;
+use crate::graph::event::GraphChange;
+use crate::graph::history::EditOperation;
+use crate::graph::node::{Behavior, NodeId};
+use crate::state::{AppState, Topology};
+use crate::utils::route_worker::{RouteListener, RouteWorker};
+
+// Operations shared by the Tauri commands and the headless server. Reads take a Topology snapshot,
+// edits go through AppState and hand back the changes so each front end can publish them its own way.
+
+#[derive(Serialize)]
+pub struct SerializableGraphState {
+    nodes: Vec<GraphNode>,
+    edges: Vec<GraphEdge>,
+    sequence: u64,
+    version: u64,
+    routes_stale: bool,
+}
+
+#[derive(Serialize)]
+pub struct GraphEdge {
+    source: String,
+    target: String,
+    cost: u32,
+    capacity: Option<u32>,
+    loss: f64,
+    reputation: f64,
+}
+
+#[derive(Serialize)]
+pub struct GraphNode {
+    id: String,
+    availability: bool,
+    compromise: f64,
+    behavior: Behavior,
+    reputation: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    centrality: Option<Centrality>,
+}
+
+#[derive(Serialize)]
+pub struct SerializablePath {
+    path: Vec<NodeId>,
+    cost: u32,
+    stale: bool,
+}
+
+pub fn get_graph(topology: &Topology, with_centrality: bool) -> SerializableGraphState {
+    let graph = &topology.graph;
+
+    let (nodes_field, edges_field) = graph.get_fields();
+    let mut centrality = if with_centrality {
+        graph.centrality()
+    } else {
+        HashMap::new()
+    };
+
+    let nodes: Vec<GraphNode> = nodes_field
+        .into_iter()
+        .map(|(node_id, node)| GraphNode {
+            id: node_id.to_string(),
+            availability: node.available,
+            compromise: node.compromise,
+            behavior: node.behavior,
+            reputation: topology.reputation.node_score(&node_id),
+            centrality: centrality.remove(&node_id),
+        })
+        .collect();
+
+    let edges: Vec<GraphEdge> = edges_field
+        .into_iter()
+        .map(|((source, target), edge)| GraphEdge {
+            source: source.to_string(),
+            target: target.to_string(),
+            cost: edge.cost,
+            capacity: edge.capacity,
+            loss: edge.loss,
+            reputation: topology.reputation.link_score(&source, &target),
+        })
+        .collect();
+
+    SerializableGraphState {
+        nodes,
+        edges,
+        sequence: topology.sequence,
+        version: topology.version,
+        routes_stale: topology.routes_stale,
+    }
+}
+
+pub fn add_node(state: &AppState, id: NodeId) -> Result<Vec<GraphChange>, String> {
+    let (_, changes) = state.edit(|topology, history| history.apply(topology.graph_mut(), &EditOperation::AddNode { id }))?;
+    Ok(changes)
+}
+
+pub fn add_edge(state: &AppState, source: NodeId, target: NodeId, cost: u32) -> Result<Vec<GraphChange>, String> {
+    let (_, changes) = state.edit(|topology, history| {
+        history.apply(topology.graph_mut(), &EditOperation::AddEdge { source, target, cost })?;
+        topology.invalidate_routes();
+        Ok(())
+    })?;
+    Ok(changes)
+}
+
+pub fn remove_node(state: &AppState, id: NodeId) -> Result<Vec<GraphChange>, String> {
+    let (_, changes) = state.edit(|topology, history| {
+        history.apply(topology.graph_mut(), &EditOperation::RemoveNode { id })?;
+        topology.invalidate_routes();
+        Ok(())
+    })?;
+    Ok(changes)
+}
+
+pub fn remove_edge(state: &AppState, source: NodeId, target: NodeId) -> Result<Vec<GraphChange>, String> {
+    let (_, changes) = state.edit(|topology, history| {
+        history.apply(topology.graph_mut(), &EditOperation::RemoveEdge { source, target })?;
+        topology.invalidate_routes();
+        Ok(())
+    })?;
+    Ok(changes)
+}
+
+pub fn set_node_availability(state: &AppState, id: NodeId, available: bool) -> Result<Vec<GraphChange>, String> {
+    let (_, changes) = state.edit(|topology, history| {
+        history.apply(topology.graph_mut(), &EditOperation::SetNodeAvailability { id, available })
+    })?;
+    Ok(changes)
+}
+
+pub fn get_shortest_path(topology: &Topology, start: NodeId, target: NodeId) -> SerializablePath {
+    let stale = topology.routes_stale;
+    if let Some(shortest_path) = topology.router.get_shortest_path(start, target) {
+        let (path, cost) = shortest_path;
+        let cost = topology.graph.path_cost(&path).unwrap_or(cost);
+        return SerializablePath { path, cost, stale }
+    }
+
+    SerializablePath { path: vec![], cost: 0, stale }
+}
+
+pub fn route_packet(topology: &Topology, start: NodeId, target: NodeId, flow: Option<u64>) -> SerializablePath {
+    let stale = topology.routes_stale;
+    let routed = match flow {
+        Some(flow) => topology.router.route_packet_flow(&start, &target, flow, &topology.graph, &topology.router.neighbors(&topology.graph)),
+        None => topology.router.route_packet_v2(&start, &target, &topology.graph),
+    };
+    if let Ok(path) = routed {
+        return SerializablePath { path, cost: 0, stale }
+    }
+
+    SerializablePath { path: vec![], cost: 0, stale }
+}
+
+pub fn after_update(listener: &impl RouteListener, state: &AppState, worker: &RouteWorker, changes: Vec<GraphChange>) {
+    listener.changes(changes);
+
+    let topology = state.read();
+    if topology.routes_stale {
+        worker.request(topology.version);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,159 @@
+use std::collections::HashSet;
+use std::sync::Arc;
+use serde::Serialize;
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+use crate::graph::route_table::RouteTable;
+
+pub const DEFAULT_ALGORITHM: &str = "dijkstra";
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+pub struct Capabilities {
+    // Link costs are u32, so no graph can hold a negative weight; this only describes the algorithm.
+    pub negative_weights: bool,
+    pub parallel: bool,
+    pub cancellable: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct AlgorithmInfo {
+    pub name: &'static str,
+    pub capabilities: Capabilities,
+    pub active: bool,
+}
+
+pub trait RoutingAlgorithm: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    fn capabilities(&self) -> Capabilities;
+
+    // Cheapest path that only crosses available nodes outside `exclude`. Both algorithms answer this
+    // with Dijkstra; the choice of algorithm only changes how `all_pairs` builds the route table.
+    fn single_pair(&self, graph: &Graph, source: &NodeId, target: &NodeId, exclude: &HashSet<NodeId>) -> Option<(Vec<NodeId>, u32)>;
+
+    // Routes between every pair, minimizing `weight` applied to each edge's cost. Returns None once
+    // `progress` asks to stop.
+    fn all_pairs(&self, graph: &Graph, weight: &dyn Fn(&NodeId, &NodeId, u32) -> u32, progress: &(dyn Fn(usize, usize) -> bool + Sync)) -> Option<RouteTable>;
+}
+
+pub struct Dijkstra;
+
+impl RoutingAlgorithm for Dijkstra {
+    fn name(&self) -> &'static str {
+        "dijkstra"
+    }
+
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { negative_weights: false, parallel: true, cancellable: true }
+    }
+
+    fn single_pair(&self, graph: &Graph, source: &NodeId, target: &NodeId, exclude: &HashSet<NodeId>) -> Option<(Vec<NodeId>, u32)> {
+        let path = graph.dijkstra_re_path(source, target, exclude)?;
+        let cost = graph.path_cost(&path)?;
+        Some((path, cost))
+    }
+
+    fn all_pairs(&self, graph: &Graph, weight: &dyn Fn(&NodeId, &NodeId, u32) -> u32, progress: &(dyn Fn(usize, usize) -> bool + Sync)) -> Option<RouteTable> {
+        graph.dijkstra_route_table_weighted(weight, progress)
+    }
+}
+
+pub struct FloydWarshall;
+
+impl RoutingAlgorithm for FloydWarshall {
+    fn name(&self) -> &'static str {
+        "floyd_warshall"
+    }
+
+    // Relaxation never assumes costs only grow along a path, so it stays correct with negative
+    // weights as long as there are no negative cycles.
+    fn capabilities(&self) -> Capabilities {
+        Capabilities { negative_weights: true, parallel: false, cancellable: true }
+    }
+
+    // Reroutes happen one hop at a time, so a single query is answered with Dijkstra instead of a
+    // whole matrix. Both find the cheapest path.
+    fn single_pair(&self, graph: &Graph, source: &NodeId, target: &NodeId, exclude: &HashSet<NodeId>) -> Option<(Vec<NodeId>, u32)> {
+        Dijkstra.single_pair(graph, source, target, exclude)
+    }
+
+    fn all_pairs(&self, graph: &Graph, weight: &dyn Fn(&NodeId, &NodeId, u32) -> u32, progress: &(dyn Fn(usize, usize) -> bool + Sync)) -> Option<RouteTable> {
+        graph.floyd_warshall_map_weighted(weight, progress).map(RouteTable::from_paths)
+    }
+}
+
+pub fn algorithms() -> Vec<Arc<dyn RoutingAlgorithm>> {
+    vec![Arc::new(Dijkstra), Arc::new(FloydWarshall)]
+}
+
+pub fn algorithm(name: &str) -> Result<Arc<dyn RoutingAlgorithm>, String> {
+    algorithms()
+        .into_iter()
+        .find(|algorithm| algorithm.name() == name)
+        .ok_or_else(|| {
+            let names: Vec<&str> = algorithms().iter().map(|algorithm| algorithm.name()).collect();
+            format!("Unknown routing algorithm {}, expected one of {}", name, names.join(", "))
+        })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::graph::graph::Graph;
+    use super::{algorithm, algorithms};
+
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph.add_edge("B".to_string(), "D".to_string(), 1).unwrap();
+        graph.add_edge("A".to_string(), "C".to_string(), 2).unwrap();
+        graph.add_edge("C".to_string(), "D".to_string(), 2).unwrap();
+        graph.add_edge("D".to_string(), "E".to_string(), 3).unwrap();
+        graph
+    }
+
+    #[test]
+    fn algorithms_agree_on_routes() {
+        let graph = graph();
+        let tables: Vec<_> = algorithms()
+            .iter()
+            .map(|algorithm| algorithm.all_pairs(&graph, &|_, _, cost| cost, &|_, _| true).unwrap())
+            .collect();
+
+        for source in tables[0].nodes() {
+            for target in tables[0].nodes() {
+                let expected = tables[0].path(source, target);
+                for table in &tables[1..] {
+                    assert_eq!(table.path(source, target), expected, "{} -> {}", source, target);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn single_pair_avoids_excluded_and_unavailable_nodes() {
+        let mut graph = graph();
+        graph.set_node_availability("C", false).unwrap();
+        let exclude: HashSet<String> = ["B".to_string()].into_iter().collect();
+
+        for algorithm in algorithms() {
+            let (path, cost) = algorithm.single_pair(&graph, &"A".to_string(), &"E".to_string(), &HashSet::new()).unwrap();
+            assert_eq!((path, cost), (vec!["A".to_string(), "B".to_string(), "D".to_string(), "E".to_string()], 5), "{}", algorithm.name());
+            assert!(algorithm.single_pair(&graph, &"A".to_string(), &"E".to_string(), &exclude).is_none(), "{}", algorithm.name());
+        }
+    }
+
+    #[test]
+    fn capabilities_tell_the_algorithms_apart() {
+        let dijkstra = algorithm("dijkstra").unwrap().capabilities();
+        let floyd_warshall = algorithm("floyd_warshall").unwrap().capabilities();
+        assert!(!dijkstra.negative_weights && dijkstra.parallel);
+        assert!(floyd_warshall.negative_weights && !floyd_warshall.parallel);
+    }
+
+    #[test]
+    fn unknown_names_are_rejected() {
+        assert_eq!(algorithm("floyd_warshall").unwrap().name(), "floyd_warshall");
+        assert!(algorithm("bellman_ford").is_err());
+    }
+}
\ No newline at end of file
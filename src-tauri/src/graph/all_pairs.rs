@@ -0,0 +1,196 @@
+use std::collections::{BinaryHeap, HashMap};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use rayon::prelude::*;
+use crate::graph::graph::{Graph, RouteMap, State};
+use crate::graph::node::NodeId;
+use crate::graph::route_table::RouteTable;
+
+impl Graph {
+    pub fn dijkstra_all_pairs(&self) -> RouteMap {
+        self.dijkstra_route_table().to_paths()
+    }
+
+    pub fn dijkstra_route_table(&self) -> RouteTable {
+        self.dijkstra_route_table_cancellable(|_, _| true).unwrap()
+    }
+
+    pub fn dijkstra_route_table_cancellable(&self, progress: impl Fn(usize, usize) -> bool + Sync) -> Option<RouteTable> {
+        self.dijkstra_route_table_weighted(|_, _, cost| cost, progress)
+    }
+
+    // One reverse Dijkstra per target on a rayon worker; `progress` is called as targets finish and
+    // stops the remaining targets when it returns false. Among equal-cost next hops the smallest
+    // NodeId wins, so every route is the lexicographically smallest of its shortest paths. `weight`
+    // maps each edge's cost to the value routing should minimize.
+    pub fn dijkstra_route_table_weighted(&self, weight: impl Fn(&NodeId, &NodeId, u32) -> u32, progress: impl Fn(usize, usize) -> bool + Sync) -> Option<RouteTable> {
+        let mut nodes: Vec<NodeId> = self.nodes.keys().cloned().collect();
+        nodes.sort();
+        let index: HashMap<&NodeId, usize> = nodes.iter().enumerate().map(|(i, id)| (id, i)).collect();
+
+        let mut forward: Vec<Vec<(usize, u32)>> = vec![Vec::new(); nodes.len()];
+        let mut reverse: Vec<Vec<(usize, u32)>> = vec![Vec::new(); nodes.len()];
+        for ((source, target), edge) in &self.edges {
+            if let (Some(&s), Some(&t)) = (index.get(source), index.get(target)) {
+                let cost = weight(source, target, edge.cost);
+                forward[s].push((t, cost));
+                reverse[t].push((s, cost));
+            }
+        }
+        for neighbors in &mut forward {
+            neighbors.sort();
+        }
+
+        let total = nodes.len();
+        let completed = AtomicUsize::new(0);
+        let cancelled = AtomicBool::new(false);
+
+        let columns: Vec<Option<(Vec<u32>, Vec<u32>)>> = (0..total)
+            .into_par_iter()
+            .map(|target| {
+                if cancelled.load(Ordering::Relaxed) {
+                    return None;
+                }
+
+                let distances = single_source(&reverse, target);
+                let next_hops = next_hops(&forward, &distances, target);
+
+                let done = completed.fetch_add(1, Ordering::Relaxed) + 1;
+                if !progress(done, total) {
+                    cancelled.store(true, Ordering::Relaxed);
+                }
+
+                Some((distances, next_hops))
+            })
+            .collect();
+
+        if cancelled.load(Ordering::Relaxed) {
+            return None;
+        }
+
+        Some(RouteTable::from_columns(nodes, columns.into_iter().flatten().collect()))
+    }
+}
+
+fn next_hops(forward: &[Vec<(usize, u32)>], distances: &[u32], target: usize) -> Vec<u32> {
+    (0..forward.len())
+        .map(|source| {
+            if source == target || distances[source] == u32::MAX {
+                return u32::MAX;
+            }
+
+            forward[source]
+                .iter()
+                .find(|&&(next, cost)| {
+                    next != source && distances[next] != u32::MAX && distances[next].checked_add(cost) == Some(distances[source])
+                })
+                .map_or(u32::MAX, |&(next, _)| next as u32)
+        })
+        .collect()
+}
+
+fn single_source(adjacency: &[Vec<(usize, u32)>], source: usize) -> Vec<u32> {
+    let mut distances = vec![u32::MAX; adjacency.len()];
+    let mut priority_queue = BinaryHeap::new();
+
+    distances[source] = 0;
+    priority_queue.push(State { node: source, cost: 0 });
+
+    while let Some(State { node: current, cost: current_distance }) = priority_queue.pop() {
+        if current_distance > distances[current] {
+            continue;
+        }
+
+        for &(next, cost) in &adjacency[current] {
+            let distance = current_distance.saturating_add(cost);
+            if distance < distances[next] {
+                distances[next] = distance;
+                priority_queue.push(State { node: next, cost: distance });
+            }
+        }
+    }
+
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::{Rng, SeedableRng};
+    use crate::graph::ecmp::WeightedNeighbors;
+    use crate::graph::graph::Graph;
+
+    fn generate(seed: u64, nodes: usize, edges: usize, max_cost: u32) -> Graph {
+        let mut rng = StdRng::seed_from_u64(seed);
+        let mut graph = Graph::new();
+
+        for i in 0..nodes {
+            graph.add_node(&format!("N{}", i)).unwrap();
+        }
+        for _ in 0..edges {
+            let source = format!("N{}", rng.gen_range(0..nodes));
+            let target = format!("N{}", rng.gen_range(0..nodes));
+            if source != target {
+                let _ = graph.add_edge(source, target, rng.gen_range(1..=max_cost));
+            }
+        }
+
+        graph
+    }
+
+    #[test]
+    fn dijkstra_all_pairs_matches_floyd_warshall_costs() {
+        for seed in 0..10 {
+            let graph = generate(seed, 30, 60, 100);
+
+            let floyd_warshall = graph.floyd_warshall_map();
+            let dijkstra = graph.dijkstra_all_pairs();
+
+            assert_eq!(floyd_warshall.len(), dijkstra.len(), "seed {}", seed);
+            for (pair, (_, cost)) in &floyd_warshall {
+                let (path, dijkstra_cost) = &dijkstra[pair];
+                assert_eq!(cost, dijkstra_cost, "seed {} pair {:?}", seed, pair);
+                assert_eq!(graph.path_cost(path), Some(*cost), "seed {} pair {:?}", seed, pair);
+            }
+        }
+    }
+
+    #[test]
+    fn huge_costs_do_not_overflow() {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), u32::MAX - 1).unwrap();
+        graph.add_edge("B".to_string(), "C".to_string(), 10).unwrap();
+        graph.add_edge("A".to_string(), "C".to_string(), 5).unwrap();
+
+        let table = graph.dijkstra_route_table();
+        assert_eq!(table.path("A", "B").map(|(_, cost)| cost), Some(15));
+        assert_eq!(table.path("B", "A").map(|(_, cost)| cost), Some(15));
+        assert_eq!(graph.floyd_warshall_map()[&("A".to_string(), "B".to_string())].1, 15);
+        assert!(graph.dijkstra_route_table_weighted(|_, _, _| u32::MAX, |_, _| true).is_some());
+        assert!(graph.floyd_warshall_map_weighted(|_, _, _| u32::MAX, |_, _| true).is_some());
+    }
+
+    #[test]
+    fn dijkstra_route_table_stops_when_cancelled() {
+        let graph = generate(42, 20, 40, 100);
+        assert!(graph.dijkstra_route_table_cancellable(|_, _| false).is_none());
+    }
+
+    #[test]
+    fn equal_cost_ties_pick_the_lexicographically_smallest_path() {
+        for seed in 0..10 {
+            let graph = generate(seed, 25, 60, 3);
+
+            let floyd_warshall = graph.floyd_warshall_map();
+            let table = graph.dijkstra_route_table();
+            let neighbors = WeightedNeighbors::new(&graph, |_, _, cost| cost);
+
+            for (pair, route) in &floyd_warshall {
+                let ecmp = table.equal_cost_paths(&graph, &neighbors, &pair.0, &pair.1, usize::MAX).unwrap();
+                assert_eq!(Some(route), table.path(&pair.0, &pair.1).as_ref(), "seed {} pair {:?}", seed, pair);
+                assert_eq!(&route.0, &ecmp.paths[0], "seed {} pair {:?}", seed, pair);
+                assert!(ecmp.paths.windows(2).all(|paths| paths[0] < paths[1]), "seed {} pair {:?}", seed, pair);
+                assert!(ecmp.paths.iter().all(|path| graph.path_cost(path) == Some(route.1)), "seed {} pair {:?}", seed, pair);
+            }
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,252 @@
+use std::collections::{BinaryHeap, HashMap, HashSet};
+use serde::Serialize;
+use crate::graph::graph::{Graph, State};
+use crate::graph::node::NodeId;
+
+const EIGENVECTOR_MAX_ITERATIONS: usize = 100;
+const EIGENVECTOR_TOLERANCE: f64 = 1e-6;
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct Centrality {
+    pub betweenness: f64,
+    pub closeness: f64,
+    pub degree: f64,
+    pub eigenvector: f64,
+}
+
+impl Graph {
+    pub fn centrality(&self) -> HashMap<NodeId, Centrality> {
+        let betweenness = self.betweenness_centrality();
+        let closeness = self.closeness_centrality();
+        let degree = self.degree_centrality();
+        let eigenvector = self.eigenvector_centrality();
+
+        self.nodes
+            .keys()
+            .map(|id| {
+                (id.clone(), Centrality {
+                    betweenness: betweenness.get(id).cloned().unwrap_or(0.0),
+                    closeness: closeness.get(id).cloned().unwrap_or(0.0),
+                    degree: degree.get(id).cloned().unwrap_or(0.0),
+                    eigenvector: eigenvector.get(id).cloned().unwrap_or(0.0),
+                })
+            })
+            .collect()
+    }
+
+    // Brandes' algorithm, with Dijkstra in place of BFS so shortest paths follow Edge::cost.
+    pub fn betweenness_centrality(&self) -> HashMap<NodeId, f64> {
+        let adjacency = self.adjacency_list();
+        let mut betweenness: HashMap<NodeId, f64> = adjacency.keys().map(|id| (id.clone(), 0.0)).collect();
+
+        for source in adjacency.keys() {
+            let mut stack: Vec<NodeId> = Vec::new();
+            let mut visited: HashSet<NodeId> = HashSet::new();
+            let mut predecessors: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+            let mut sigma: HashMap<NodeId, f64> = HashMap::new();
+            let mut distances: HashMap<NodeId, u32> = HashMap::new();
+            let mut priority_queue = BinaryHeap::new();
+
+            sigma.insert(source.clone(), 1.0);
+            distances.insert(source.clone(), 0);
+            priority_queue.push(State { node: source.clone(), cost: 0 });
+
+            while let Some(State { node: current_node, cost: current_distance }) = priority_queue.pop() {
+                if current_distance > distances[&current_node] || !visited.insert(current_node.clone()) {
+                    continue;
+                }
+                stack.push(current_node.clone());
+
+                for (neighbor, cost) in &adjacency[&current_node] {
+                    let distance = current_distance + cost;
+                    let known_distance = distances.get(neighbor).cloned().unwrap_or(u32::MAX);
+
+                    if distance < known_distance {
+                        distances.insert(neighbor.clone(), distance);
+                        sigma.insert(neighbor.clone(), sigma[&current_node]);
+                        predecessors.insert(neighbor.clone(), vec![current_node.clone()]);
+                        priority_queue.push(State { node: neighbor.clone(), cost: distance });
+                    } else if distance == known_distance {
+                        *sigma.entry(neighbor.clone()).or_insert(0.0) += sigma[&current_node];
+                        predecessors.entry(neighbor.clone()).or_default().push(current_node.clone());
+                    }
+                }
+            }
+
+            let mut delta: HashMap<NodeId, f64> = HashMap::new();
+            while let Some(node) = stack.pop() {
+                let node_delta = delta.get(&node).cloned().unwrap_or(0.0);
+                if let Some(node_predecessors) = predecessors.get(&node) {
+                    for predecessor in node_predecessors {
+                        let contribution = sigma[predecessor] / sigma[&node] * (1.0 + node_delta);
+                        *delta.entry(predecessor.clone()).or_insert(0.0) += contribution;
+                    }
+                }
+
+                if &node != source {
+                    *betweenness.get_mut(&node).unwrap() += node_delta;
+                }
+            }
+        }
+
+        let n = adjacency.len() as f64;
+        if n > 2.0 {
+            let scale = 1.0 / ((n - 1.0) * (n - 2.0));
+            for value in betweenness.values_mut() {
+                *value *= scale;
+            }
+        }
+
+        betweenness
+    }
+
+    // Wasserman-Faust closeness, so nodes in small components are not ranked above well-connected ones.
+    pub fn closeness_centrality(&self) -> HashMap<NodeId, f64> {
+        let adjacency = self.adjacency_list();
+        let n = adjacency.len();
+
+        adjacency
+            .keys()
+            .map(|source| {
+                let distances = shortest_distances(&adjacency, source);
+                let reachable = distances.len() - 1;
+                let total: u64 = distances.values().map(|&d| d as u64).sum();
+
+                let closeness = if reachable == 0 || total == 0 {
+                    0.0
+                } else {
+                    (reachable as f64 / total as f64) * (reachable as f64 / (n - 1) as f64)
+                };
+
+                (source.clone(), closeness)
+            })
+            .collect()
+    }
+
+    pub fn degree_centrality(&self) -> HashMap<NodeId, f64> {
+        let adjacency = self.adjacency_list();
+        let n = adjacency.len();
+
+        adjacency
+            .iter()
+            .map(|(id, neighbors)| {
+                let degree = if n > 1 { neighbors.len() as f64 / (n - 1) as f64 } else { 0.0 };
+                (id.clone(), degree)
+            })
+            .collect()
+    }
+
+    // Power iteration where each link weighs 1 / cost, so cheap links transfer more influence.
+    pub fn eigenvector_centrality(&self) -> HashMap<NodeId, f64> {
+        let adjacency = self.adjacency_list();
+        if adjacency.is_empty() {
+            return HashMap::new();
+        }
+
+        let initial = 1.0 / adjacency.len() as f64;
+        let mut scores: HashMap<NodeId, f64> = adjacency.keys().map(|id| (id.clone(), initial)).collect();
+
+        for _ in 0..EIGENVECTOR_MAX_ITERATIONS {
+            let mut next: HashMap<NodeId, f64> = scores.clone();
+            for (source, neighbors) in &adjacency {
+                for (target, cost) in neighbors {
+                    *next.get_mut(target).unwrap() += scores[source] / (*cost).max(1) as f64;
+                }
+            }
+
+            let norm = next.values().map(|v| v * v).sum::<f64>().sqrt();
+            if norm == 0.0 {
+                return next;
+            }
+            for value in next.values_mut() {
+                *value /= norm;
+            }
+
+            let change: f64 = next.iter().map(|(id, v)| (v - scores[id]).abs()).sum();
+            scores = next;
+            if change < EIGENVECTOR_TOLERANCE * adjacency.len() as f64 {
+                break;
+            }
+        }
+
+        scores
+    }
+}
+
+fn shortest_distances(adjacency: &HashMap<NodeId, Vec<(NodeId, u32)>>, source: &NodeId) -> HashMap<NodeId, u32> {
+    let mut distances: HashMap<NodeId, u32> = HashMap::new();
+    let mut priority_queue = BinaryHeap::new();
+
+    distances.insert(source.clone(), 0);
+    priority_queue.push(State { node: source.clone(), cost: 0 });
+
+    while let Some(State { node: current_node, cost: current_distance }) = priority_queue.pop() {
+        if current_distance > distances[&current_node] {
+            continue;
+        }
+
+        for (neighbor, cost) in &adjacency[&current_node] {
+            let distance = current_distance + cost;
+            if distances.get(neighbor).map_or(true, |&d| distance < d) {
+                distances.insert(neighbor.clone(), distance);
+                priority_queue.push(State { node: neighbor.clone(), cost: distance });
+            }
+        }
+    }
+
+    distances
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashMap;
+    use crate::graph::graph::Graph;
+
+    fn path() -> Graph {
+        let mut graph = Graph::new();
+        for (source, target) in [("A", "B"), ("B", "C"), ("C", "D"), ("D", "E")] {
+            graph.add_edge(source.to_string(), target.to_string(), 1).unwrap();
+        }
+        graph
+    }
+
+    fn assert_scores(scores: &HashMap<String, f64>, expected: &[(&str, f64)]) {
+        for (id, value) in expected {
+            assert!((scores[*id] - value).abs() < 1e-9, "{}: {} != {}", id, scores[*id], value);
+        }
+    }
+
+    #[test]
+    fn path_graph_scores() {
+        let graph = path();
+
+        assert_scores(&graph.betweenness_centrality(), &[("A", 0.0), ("B", 0.5), ("C", 4.0 / 6.0), ("D", 0.5), ("E", 0.0)]);
+        assert_scores(&graph.closeness_centrality(), &[("A", 0.4), ("B", 4.0 / 7.0), ("C", 4.0 / 6.0)]);
+        assert_scores(&graph.degree_centrality(), &[("A", 0.25), ("C", 0.5)]);
+    }
+
+    #[test]
+    fn parallel_shortest_paths_split_betweenness() {
+        let mut graph = Graph::new();
+        for (source, target) in [("A", "B"), ("A", "C"), ("B", "D"), ("C", "D")] {
+            graph.add_edge(source.to_string(), target.to_string(), 1).unwrap();
+        }
+
+        // A and D are joined through B and through C, each carrying half of both directions.
+        assert_scores(&graph.betweenness_centrality(), &[("A", 1.0 / 6.0), ("B", 1.0 / 6.0), ("C", 1.0 / 6.0), ("D", 1.0 / 6.0)]);
+    }
+
+    #[test]
+    fn the_hub_of_a_star_has_the_highest_eigenvector_score() {
+        let mut graph = Graph::new();
+        for leaf in ["B", "C", "D", "E"] {
+            graph.add_edge("A".to_string(), leaf.to_string(), 1).unwrap();
+        }
+
+        let scores = graph.eigenvector_centrality();
+        for leaf in ["B", "C", "D", "E"] {
+            assert!(scores["A"] > scores[leaf]);
+            assert!((scores[leaf] - scores["B"]).abs() < 1e-6);
+        }
+    }
+}
\ No newline at end of file
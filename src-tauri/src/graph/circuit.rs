@@ -0,0 +1,258 @@
+use std::collections::{BTreeMap, HashSet};
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+use serde::{Deserialize, Serialize};
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+
+const DEFAULT_CIRCUIT_LENGTH: usize = 3;
+const MAX_ATTEMPTS: usize = 32;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct CircuitConstraints {
+    pub length: Option<usize>,
+    pub entry: Option<NodeId>,
+    pub exit: Option<NodeId>,
+    pub max_compromise: Option<f64>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Copy, Debug, PartialEq)]
+#[serde(rename_all = "snake_case")]
+pub enum RelayRole {
+    Entry,
+    Middle,
+    Exit,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CircuitLeg {
+    pub from: NodeId,
+    pub to: NodeId,
+    pub path: Vec<NodeId>,
+    pub cost: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Observer {
+    pub id: NodeId,
+    pub role: Option<RelayRole>,
+    pub sees_source: bool,
+    pub sees_destination: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Circuit {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub relays: Vec<NodeId>,
+    pub legs: Vec<CircuitLeg>,
+    pub hops: Vec<NodeId>,
+    pub cost: u32,
+    pub observers: Vec<Observer>,
+    pub deanonymizers: Vec<NodeId>,
+}
+
+impl Graph {
+    // Relays are drawn at random from the available nodes that pass the trust threshold, then each
+    // leg between consecutive relays is routed on its own shortest path. Draws whose legs cannot be
+    // routed are retried with a fresh selection.
+    pub fn build_circuit(&self, source: &str, target: &str, constraints: &CircuitConstraints) -> Result<Circuit, String> {
+        for id in [source, target] {
+            if !self.is_node_available(&id.to_string()) {
+                return Err(format!("Node {} is not available", id));
+            }
+        }
+        if source == target {
+            return Err("Source and target must be different".to_string());
+        }
+
+        let length = constraints.length.unwrap_or(DEFAULT_CIRCUIT_LENGTH);
+        if length < 2 {
+            return Err("A circuit needs at least an entry and an exit relay".to_string());
+        }
+
+        let max_compromise = constraints.max_compromise.unwrap_or(1.0);
+        let eligible = |id: &NodeId| {
+            id != source && id != target && self.nodes.get(id).map_or(false, |node| node.available && node.compromise <= max_compromise)
+        };
+
+        for pinned in constraints.entry.iter().chain(constraints.exit.iter()) {
+            if !eligible(pinned) {
+                return Err(format!("Node {} cannot be used as a relay", pinned));
+            }
+        }
+        if constraints.entry.is_some() && constraints.entry == constraints.exit {
+            return Err("Entry and exit relays must be different".to_string());
+        }
+
+        let mut candidates: Vec<NodeId> = self.nodes
+            .keys()
+            .filter(|id| eligible(id) && Some(*id) != constraints.entry.as_ref() && Some(*id) != constraints.exit.as_ref())
+            .cloned()
+            .collect();
+        candidates.sort();
+
+        let drawn = length - constraints.entry.iter().count() - constraints.exit.iter().count();
+        if candidates.len() < drawn {
+            return Err(format!("Not enough eligible relays for a circuit of length {}", length));
+        }
+
+        let mut rng = match constraints.seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        for _ in 0..MAX_ATTEMPTS {
+            candidates.shuffle(&mut rng);
+            let mut picked = candidates[..drawn].iter().cloned();
+
+            let mut relays = Vec::with_capacity(length);
+            relays.push(constraints.entry.clone().or_else(|| picked.next()).unwrap());
+            relays.extend(picked.by_ref().take(length - 2));
+            relays.push(constraints.exit.clone().or_else(|| picked.next()).unwrap());
+
+            if let Some(circuit) = self.route_circuit(source, target, relays) {
+                return Ok(circuit);
+            }
+        }
+
+        Err(format!("Could not build a circuit from {} to {} after {} attempts", source, target, MAX_ATTEMPTS))
+    }
+
+    fn route_circuit(&self, source: &str, target: &str, relays: Vec<NodeId>) -> Option<Circuit> {
+        let mut waypoints = vec![source.to_string()];
+        waypoints.extend(relays.iter().cloned());
+        waypoints.push(target.to_string());
+
+        // A leg that ran through another waypoint would let that node see more of the circuit than
+        // its position allows, so each leg avoids all the others.
+        let mut legs = Vec::new();
+        for pair in waypoints.windows(2) {
+            let others: HashSet<NodeId> = waypoints.iter().filter(|id| !pair.contains(id)).cloned().collect();
+            let path = self.dijkstra_re_path(&pair[0], &pair[1], &others)?;
+            let cost = path.windows(2).map(|hop| self.edges[&(hop[0].clone(), hop[1].clone())].cost).sum();
+            legs.push(CircuitLeg { from: pair[0].clone(), to: pair[1].clone(), path, cost });
+        }
+
+        let mut hops = vec![source.to_string()];
+        for leg in &legs {
+            hops.extend(leg.path[1..].iter().cloned());
+        }
+
+        // Everything on the first leg sees who is sending, everything on the last leg sees where
+        // the traffic goes; relays and routers in between only see onion-wrapped traffic.
+        let mut observers: BTreeMap<NodeId, Observer> = BTreeMap::new();
+        for (index, leg) in legs.iter().enumerate() {
+            for id in &leg.path {
+                if id == source || id == target {
+                    continue;
+                }
+
+                let role = relays.iter().position(|relay| relay == id).map(|position| match position {
+                    0 => RelayRole::Entry,
+                    position if position == relays.len() - 1 => RelayRole::Exit,
+                    _ => RelayRole::Middle,
+                });
+                let observer = observers.entry(id.clone()).or_insert(Observer {
+                    id: id.clone(),
+                    role,
+                    sees_source: false,
+                    sees_destination: false,
+                });
+                observer.sees_source |= index == 0;
+                observer.sees_destination |= index == legs.len() - 1;
+            }
+        }
+
+        let deanonymizers = observers
+            .values()
+            .filter(|observer| observer.sees_source && observer.sees_destination)
+            .map(|observer| observer.id.clone())
+            .collect();
+
+        Some(Circuit {
+            source: source.to_string(),
+            target: target.to_string(),
+            relays,
+            cost: legs.iter().map(|leg| leg.cost).sum(),
+            legs,
+            hops,
+            observers: observers.into_values().collect(),
+            deanonymizers,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+    use super::CircuitConstraints;
+
+    // S and T joined through five relays that all link to each other; R5 is likely compromised.
+    fn mesh() -> Graph {
+        let mut graph = Graph::new();
+        let ids = ["S", "T", "R1", "R2", "R3", "R4", "R5"];
+        for (i, source) in ids.iter().enumerate() {
+            for target in &ids[i + 1..] {
+                graph.add_edge(source.to_string(), target.to_string(), 1).unwrap();
+            }
+        }
+        graph.set_node_compromise("R5", 0.9).unwrap();
+        graph
+    }
+
+    fn constraints(length: usize, seed: u64) -> CircuitConstraints {
+        CircuitConstraints { length: Some(length), max_compromise: Some(0.5), seed: Some(seed), ..Default::default() }
+    }
+
+    #[test]
+    fn relays_are_distinct_trusted_and_honour_pinned_ends() {
+        let graph = mesh();
+        for seed in 0..16 {
+            let circuit = graph.build_circuit("S", "T", &CircuitConstraints {
+                entry: Some("R2".to_string()),
+                exit: Some("R3".to_string()),
+                ..constraints(4, seed)
+            }).unwrap();
+
+            let relays = &circuit.relays;
+            assert_eq!(relays.len(), 4);
+            assert_eq!((relays[0].as_str(), relays[3].as_str()), ("R2", "R3"));
+            assert!(relays[1] != relays[2]);
+            assert!(relays.iter().all(|relay| relay != "R5" && relay != "S" && relay != "T"));
+            assert_eq!(circuit.hops.len(), 6);
+        }
+
+        let circuit = graph.build_circuit("S", "T", &constraints(4, 3)).unwrap();
+        assert_eq!(graph.build_circuit("S", "T", &constraints(4, 3)).unwrap().relays, circuit.relays);
+    }
+
+    #[test]
+    fn too_few_eligible_relays_is_an_error() {
+        let graph = mesh();
+        assert!(graph.build_circuit("S", "T", &constraints(5, 1)).is_err());
+        assert!(graph.build_circuit("S", "T", &CircuitConstraints { max_compromise: None, ..constraints(5, 1) }).is_ok());
+        assert!(graph.build_circuit("S", "T", &CircuitConstraints { entry: Some("R5".to_string()), ..constraints(2, 1) }).is_err());
+    }
+
+    #[test]
+    fn legs_do_not_cross_other_waypoints() {
+        // The cheapest way from S to B runs through A and from A to T through B.
+        let mut graph = Graph::new();
+        for (source, target, cost) in [("S", "A", 1), ("A", "B", 1), ("B", "T", 1), ("S", "C", 3), ("C", "B", 3), ("A", "D", 3), ("D", "T", 3)] {
+            graph.add_edge(source.to_string(), target.to_string(), cost).unwrap();
+        }
+
+        let circuit = graph.build_circuit("S", "T", &CircuitConstraints {
+            length: Some(2),
+            entry: Some("B".to_string()),
+            exit: Some("A".to_string()),
+            ..Default::default()
+        }).unwrap();
+        assert_eq!(circuit.hops, vec!["S", "C", "B", "A", "D", "T"]);
+        assert_eq!(circuit.cost, 13);
+        assert!(circuit.deanonymizers.is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,132 @@
+use std::collections::HashMap;
+use serde::Serialize;
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+use crate::graph::route_table::RouteTable;
+
+pub const DEFAULT_ECMP_LIMIT: usize = 16;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EqualCostPaths {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub cost: u32,
+    pub paths: Vec<Vec<NodeId>>,
+    pub truncated: bool,
+}
+
+impl EqualCostPaths {
+    // The same flow always hashes to the same path, different flows spread across all of them.
+    pub fn select(&self, flow: u64) -> &Vec<NodeId> {
+        let hash = flow_hash(&self.source, &self.target, flow);
+        &self.paths[(hash % self.paths.len() as u64) as usize]
+    }
+}
+
+// FNV-1a, so flow placement stays stable across runs and Rust versions.
+pub fn flow_hash(source: &str, target: &str, flow: u64) -> u64 {
+    let mut hash: u64 = 0xcbf29ce484222325;
+    let bytes = source.bytes().chain([0xff]).chain(target.bytes()).chain([0xff]).chain(flow.to_le_bytes());
+    for byte in bytes {
+        hash ^= byte as u64;
+        hash = hash.wrapping_mul(0x100000001b3);
+    }
+
+    hash
+}
+
+// Each node's links with the weight the route table was computed with, in the order the search
+// tries them. Building it walks every edge, so callers routing many flows over one graph build it
+// once.
+pub struct WeightedNeighbors {
+    neighbors: HashMap<NodeId, Vec<(NodeId, u32)>>,
+}
+
+impl WeightedNeighbors {
+    pub fn new(graph: &Graph, weight: impl Fn(&NodeId, &NodeId, u32) -> u32) -> WeightedNeighbors {
+        let mut neighbors: HashMap<NodeId, Vec<(NodeId, u32)>> = HashMap::new();
+        for ((from, to), edge) in &graph.edges {
+            neighbors.entry(from.clone()).or_default().push((to.clone(), weight(from, to, edge.cost)));
+        }
+        for candidates in neighbors.values_mut() {
+            candidates.sort();
+        }
+
+        WeightedNeighbors { neighbors }
+    }
+}
+
+impl RouteTable {
+    // Every path whose hops all stay on a shortest route to the target, in lexicographic order,
+    // so the first one is the path the table itself returns. Stops after `limit` paths. The
+    // reported cost is the paths' raw edge cost.
+    pub fn equal_cost_paths(&self, graph: &Graph, neighbors: &WeightedNeighbors, source: &str, target: &str, limit: usize) -> Option<EqualCostPaths> {
+        if source == target || limit == 0 {
+            return None;
+        }
+        self.distance(source, target)?;
+
+        let neighbors = &neighbors.neighbors;
+        let mut search = EqualCostSearch { table: self, neighbors, target, limit, paths: Vec::new(), truncated: false };
+        search.extend(&mut vec![source.to_string()]);
+
+        let cost = graph.path_cost(search.paths.first()?)?;
+
+        Some(EqualCostPaths {
+            source: source.to_string(),
+            target: target.to_string(),
+            cost,
+            paths: search.paths,
+            truncated: search.truncated,
+        })
+    }
+}
+
+struct EqualCostSearch<'a> {
+    table: &'a RouteTable,
+    neighbors: &'a HashMap<NodeId, Vec<(NodeId, u32)>>,
+    target: &'a str,
+    limit: usize,
+    paths: Vec<Vec<NodeId>>,
+    truncated: bool,
+}
+
+impl EqualCostSearch<'_> {
+    fn extend(&mut self, path: &mut Vec<NodeId>) {
+        if self.truncated {
+            return;
+        }
+
+        let current = path.last().unwrap().clone();
+        if current == self.target {
+            if self.paths.len() == self.limit {
+                self.truncated = true;
+            } else {
+                self.paths.push(path.clone());
+            }
+            return;
+        }
+
+        let remaining = match self.table.distance(&current, self.target) {
+            Some(remaining) => remaining,
+            None => return,
+        };
+
+        let candidates: Vec<NodeId> = self.neighbors
+            .get(&current)
+            .into_iter()
+            .flatten()
+            .filter(|(next, cost)| {
+                !path.contains(next)
+                    && self.table.distance(next, self.target).and_then(|rest| rest.checked_add(*cost)) == Some(remaining)
+            })
+            .map(|(next, _)| next.clone())
+            .collect();
+
+        for next in candidates {
+            path.push(next);
+            self.extend(path);
+            path.pop();
+        }
+    }
+}
\ No newline at end of file
@@ -1,11 +1,15 @@
-use serde::Serialize;
+use serde::{Deserialize, Serialize};
 use crate::graph::node::NodeId;
 
-#[derive(Serialize, Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Edge {
     pub source: NodeId,
     pub target: NodeId,
     pub cost: u32,
+    #[serde(default)]
+    pub capacity: Option<u32>,
+    #[serde(default)]
+    pub loss: f64,
 }
 
 impl Edge {
@@ -13,7 +17,9 @@ impl Edge {
         Self {
             source,
             target,
-            cost
+            cost,
+            capacity: None,
+            loss: 0.0,
         }
     }
 }
\ No newline at end of file
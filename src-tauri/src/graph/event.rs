@@ -0,0 +1,28 @@
+use serde::Serialize;
+use crate::graph::node::{Behavior, NodeId};
+
+pub const GRAPH_CHANGED_EVENT: &str = "graph-changed";
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "type")]
+pub enum GraphEvent {
+    NodeAdded { id: NodeId },
+    NodeRemoved { id: NodeId },
+    EdgeAdded { source: NodeId, target: NodeId, cost: u32 },
+    EdgeRemoved { source: NodeId, target: NodeId },
+    CapacityChanged { source: NodeId, target: NodeId, capacity: Option<u32> },
+    LossChanged { source: NodeId, target: NodeId, loss: f64 },
+    AvailabilityChanged { id: NodeId, available: bool },
+    CompromiseChanged { id: NodeId, compromise: f64 },
+    BehaviorChanged { id: NodeId, behavior: Behavior },
+    RoutesRecomputed { routes: usize },
+    ReputationUpdated,
+    AlgorithmChanged { name: String },
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct GraphChange {
+    pub sequence: u64,
+    #[serde(flatten)]
+    pub event: GraphEvent,
+}
\ No newline at end of file
@@ -0,0 +1,186 @@
+use std::collections::{HashMap, HashSet};
+use serde::{Deserialize, Serialize};
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+
+#[derive(Serialize, Deserialize, Clone, Debug, Default)]
+pub struct FailureSet {
+    #[serde(default)]
+    pub nodes: Vec<NodeId>,
+    #[serde(default)]
+    pub edges: Vec<(NodeId, NodeId)>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PairImpact {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub before_path: Vec<NodeId>,
+    pub before_cost: u32,
+    pub after_path: Option<Vec<NodeId>>,
+    pub after_cost: Option<u32>,
+    pub cost_delta: Option<i64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FailureImpact {
+    pub failures: FailureSet,
+    pub unreachable: Vec<PairImpact>,
+    pub rerouted: Vec<PairImpact>,
+    pub total_cost_delta: i64,
+}
+
+impl Graph {
+    // The graph as routing sees it: unavailable nodes and the links touching them are gone.
+    pub fn available_view(&self) -> Graph {
+        let mut graph = self.clone();
+        graph.nodes.retain(|_, node| node.available);
+        graph.edges.retain(|(source, target), _| graph.nodes.contains_key(source) && graph.nodes.contains_key(target));
+        graph
+    }
+
+    pub fn with_failures(&self, failures: &FailureSet) -> Result<Graph, String> {
+        let mut graph = self.clone();
+
+        for node_id in &failures.nodes {
+            if graph.nodes.remove(node_id).is_none() {
+                return Err(format!("Node {} does not exist", node_id));
+            }
+            graph.edges.retain(|(source, target), _| source != node_id && target != node_id);
+        }
+
+        for (source, target) in &failures.edges {
+            let forward = graph.edges.remove(&(source.clone(), target.clone()));
+            let backward = graph.edges.remove(&(target.clone(), source.clone()));
+            if forward.is_none() && backward.is_none() && !failures.nodes.contains(source) && !failures.nodes.contains(target) {
+                return Err(format!("Edge {} -> {} does not exist", source, target));
+            }
+        }
+
+        Ok(graph)
+    }
+
+    // Both tables route around nodes that are already unavailable, the way live routing does.
+    pub fn failure_impact(&self, failures: &FailureSet) -> Result<FailureImpact, String> {
+        self.failure_impact_against(&self.available_view().floyd_warshall_map(), failures)
+    }
+
+    // `before` is the available view's all-pairs table, so callers comparing many failures compute
+    // it once.
+    fn failure_impact_against(&self, before: &HashMap<(NodeId, NodeId), (Vec<NodeId>, u32)>, failures: &FailureSet) -> Result<FailureImpact, String> {
+        let failed_graph = self.with_failures(failures)?;
+        let failed_nodes: HashSet<&NodeId> = failures.nodes.iter().collect();
+
+        let after = failed_graph.available_view().floyd_warshall_map();
+
+        let mut unreachable = Vec::new();
+        let mut rerouted = Vec::new();
+        let mut total_cost_delta = 0;
+
+        for ((source, target), (before_path, before_cost)) in before {
+            if failed_nodes.contains(source) || failed_nodes.contains(target) {
+                continue;
+            }
+            let (source, target, before_path, before_cost) = (source.clone(), target.clone(), before_path.clone(), *before_cost);
+
+            match after.get(&(source.clone(), target.clone())) {
+                None => unreachable.push(PairImpact {
+                    source,
+                    target,
+                    before_path,
+                    before_cost,
+                    after_path: None,
+                    after_cost: None,
+                    cost_delta: None,
+                }),
+                Some((after_path, after_cost)) if *after_path != before_path => {
+                    let cost_delta = *after_cost as i64 - before_cost as i64;
+                    total_cost_delta += cost_delta;
+                    rerouted.push(PairImpact {
+                        source,
+                        target,
+                        before_path,
+                        before_cost,
+                        after_path: Some(after_path.clone()),
+                        after_cost: Some(*after_cost),
+                        cost_delta: Some(cost_delta),
+                    });
+                }
+                Some(_) => {}
+            }
+        }
+
+        unreachable.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+        rerouted.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+        Ok(FailureImpact { failures: failures.clone(), unreachable, rerouted, total_cost_delta })
+    }
+
+    pub fn rank_single_node_failures(&self) -> Vec<FailureImpact> {
+        let before = self.available_view().floyd_warshall_map();
+        let mut impacts: Vec<FailureImpact> = self.get_node_ids()
+            .into_iter()
+            .filter(|node_id| self.is_node_available(node_id))
+            .filter_map(|node_id| {
+                let failures = FailureSet { nodes: vec![node_id], edges: vec![] };
+                self.failure_impact_against(&before, &failures).ok()
+            })
+            .collect();
+
+        impacts.sort_by(|a, b| {
+            b.unreachable.len().cmp(&a.unreachable.len())
+                .then(b.total_cost_delta.cmp(&a.total_cost_delta))
+                .then(b.rerouted.len().cmp(&a.rerouted.len()))
+        });
+
+        impacts
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+    use super::FailureSet;
+
+    #[test]
+    fn the_hub_of_a_star_ranks_first() {
+        let mut graph = Graph::new();
+        for leaf in ["B", "C", "D"] {
+            graph.add_edge("A".to_string(), leaf.to_string(), 1).unwrap();
+        }
+        graph.add_edge("C".to_string(), "D".to_string(), 5).unwrap();
+
+        let ranking = graph.rank_single_node_failures();
+        assert_eq!(ranking.len(), 4);
+        assert_eq!(ranking[0].failures.nodes, vec!["A".to_string()]);
+        // B is cut off from C and D, which reroute over their own link.
+        assert_eq!(ranking[0].unreachable.len(), 4);
+        assert_eq!(ranking[0].rerouted.len(), 2);
+        assert_eq!(ranking[0].total_cost_delta, 6);
+        assert!(ranking[1..].iter().all(|impact| impact.unreachable.is_empty() && impact.rerouted.is_empty()));
+    }
+
+    #[test]
+    fn nodes_that_are_already_down_carry_no_traffic() {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph.add_edge("B".to_string(), "C".to_string(), 1).unwrap();
+        graph.add_edge("A".to_string(), "D".to_string(), 2).unwrap();
+        graph.add_edge("D".to_string(), "C".to_string(), 2).unwrap();
+        graph.set_node_availability("B", false).unwrap();
+
+        let ranking = graph.rank_single_node_failures();
+        assert!(ranking.iter().all(|impact| impact.failures.nodes != vec!["B".to_string()]));
+        assert_eq!(ranking[0].failures.nodes, vec!["D".to_string()]);
+        assert_eq!(ranking[0].unreachable.len(), 2);
+
+        let impact = graph.failure_impact(&FailureSet { nodes: vec![], edges: vec![("A".to_string(), "D".to_string())] }).unwrap();
+        assert_eq!(impact.unreachable.len(), 4);
+    }
+
+    #[test]
+    fn unknown_failures_are_rejected() {
+        let graph = Graph::new();
+        assert!(graph.failure_impact(&FailureSet { nodes: vec!["A".to_string()], edges: vec![] }).is_err());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,193 @@
+use std::collections::{HashMap, HashSet, VecDeque};
+use serde::Serialize;
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+
+const DEFAULT_CAPACITY: u32 = 1;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EdgeFlow {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub flow: u64,
+    pub capacity: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct MaxFlow {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub value: u64,
+    pub flows: Vec<EdgeFlow>,
+    pub cut: Vec<(NodeId, NodeId)>,
+    pub source_side: Vec<NodeId>,
+}
+
+impl Graph {
+    // Edmonds-Karp over the available nodes; edges without a capacity count as one channel each.
+    pub fn max_flow(&self, source: &str, target: &str) -> Result<MaxFlow, String> {
+        for id in [source, target] {
+            match self.nodes.get(id) {
+                None => return Err(format!("Node {} does not exist", id)),
+                Some(node) if !node.available => return Err(format!("Node {} is not available", id)),
+                _ => {}
+            }
+        }
+        if source == target {
+            return Err("Source and target must be different nodes".to_string());
+        }
+
+        let capacities: HashMap<(NodeId, NodeId), u64> = self.edges
+            .iter()
+            .filter(|((from, to), _)| self.is_node_available(from) && self.is_node_available(to))
+            .map(|(key, edge)| (key.clone(), edge.capacity.unwrap_or(DEFAULT_CAPACITY) as u64))
+            .collect();
+
+        let mut residual: HashMap<(NodeId, NodeId), u64> = HashMap::new();
+        let mut neighbors: HashMap<NodeId, Vec<NodeId>> = HashMap::new();
+        for ((from, to), capacity) in &capacities {
+            *residual.entry((from.clone(), to.clone())).or_insert(0) += capacity;
+            residual.entry((to.clone(), from.clone())).or_insert(0);
+            neighbors.entry(from.clone()).or_default().push(to.clone());
+            neighbors.entry(to.clone()).or_default().push(from.clone());
+        }
+        for list in neighbors.values_mut() {
+            list.sort();
+            list.dedup();
+        }
+
+        let source = source.to_string();
+        let target = target.to_string();
+        let mut value = 0;
+
+        while let Some(path) = augmenting_path(&residual, &neighbors, &source, &target) {
+            let bottleneck = path.windows(2)
+                .map(|hop| residual[&(hop[0].clone(), hop[1].clone())])
+                .min()
+                .unwrap_or(0);
+
+            for hop in path.windows(2) {
+                *residual.get_mut(&(hop[0].clone(), hop[1].clone())).unwrap() -= bottleneck;
+                *residual.get_mut(&(hop[1].clone(), hop[0].clone())).unwrap() += bottleneck;
+            }
+            value += bottleneck;
+        }
+
+        let mut flows: Vec<EdgeFlow> = capacities
+            .iter()
+            .filter_map(|((from, to), &capacity)| {
+                let remaining = residual[&(from.clone(), to.clone())];
+                if remaining < capacity {
+                    Some(EdgeFlow { source: from.clone(), target: to.clone(), flow: capacity - remaining, capacity })
+                } else {
+                    None
+                }
+            })
+            .collect();
+        flows.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+        let reachable = reachable_nodes(&residual, &neighbors, &source);
+        let mut cut: Vec<(NodeId, NodeId)> = capacities
+            .iter()
+            .filter(|((from, to), &capacity)| capacity > 0 && reachable.contains(from) && !reachable.contains(to))
+            .map(|(key, _)| key.clone())
+            .collect();
+        cut.sort();
+
+        let mut source_side: Vec<NodeId> = reachable.into_iter().collect();
+        source_side.sort();
+
+        Ok(MaxFlow { source, target, value, flows, cut, source_side })
+    }
+}
+
+fn augmenting_path(residual: &HashMap<(NodeId, NodeId), u64>, neighbors: &HashMap<NodeId, Vec<NodeId>>, source: &NodeId, target: &NodeId) -> Option<Vec<NodeId>> {
+    let mut predecessors: HashMap<NodeId, NodeId> = HashMap::new();
+    let mut visited: HashSet<NodeId> = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    visited.insert(source.clone());
+    queue.push_back(source.clone());
+
+    while let Some(current) = queue.pop_front() {
+        if &current == target {
+            let mut path = vec![current.clone()];
+            let mut node = current;
+            while let Some(previous) = predecessors.get(&node) {
+                path.push(previous.clone());
+                node = previous.clone();
+            }
+            path.reverse();
+            return Some(path);
+        }
+
+        for next in neighbors.get(&current).into_iter().flatten() {
+            if residual[&(current.clone(), next.clone())] > 0 && visited.insert(next.clone()) {
+                predecessors.insert(next.clone(), current.clone());
+                queue.push_back(next.clone());
+            }
+        }
+    }
+
+    None
+}
+
+fn reachable_nodes(residual: &HashMap<(NodeId, NodeId), u64>, neighbors: &HashMap<NodeId, Vec<NodeId>>, source: &NodeId) -> HashSet<NodeId> {
+    let mut visited: HashSet<NodeId> = HashSet::new();
+    let mut queue = VecDeque::new();
+
+    visited.insert(source.clone());
+    queue.push_back(source.clone());
+
+    while let Some(current) = queue.pop_front() {
+        for next in neighbors.get(&current).into_iter().flatten() {
+            if residual[&(current.clone(), next.clone())] > 0 && visited.insert(next.clone()) {
+                queue.push_back(next.clone());
+            }
+        }
+    }
+
+    visited
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+
+    fn link(graph: &mut Graph, source: &str, target: &str, capacity: Option<u32>) {
+        graph.add_edge(source.to_string(), target.to_string(), 1).unwrap();
+        graph.set_edge_capacity(source, target, capacity).unwrap();
+    }
+
+    #[test]
+    fn max_flow_matches_the_min_cut() {
+        let mut graph = Graph::new();
+        link(&mut graph, "S", "A", Some(5));
+        link(&mut graph, "S", "B", Some(5));
+        link(&mut graph, "A", "B", Some(1));
+        link(&mut graph, "A", "T", Some(2));
+        link(&mut graph, "B", "T", Some(2));
+
+        let flow = graph.max_flow("S", "T").unwrap();
+        assert_eq!(flow.value, 4);
+        assert_eq!(flow.cut, vec![("A".to_string(), "T".to_string()), ("B".to_string(), "T".to_string())]);
+        assert_eq!(flow.source_side, vec!["A", "B", "S"]);
+        let into_target: u64 = flow.flows.iter().filter(|edge| edge.target == "T").map(|edge| edge.flow).sum();
+        assert_eq!(into_target, 4);
+    }
+
+    #[test]
+    fn links_without_capacity_carry_one_unit_and_unavailable_nodes_none() {
+        let mut graph = Graph::new();
+        for relay in ["A", "B", "C"] {
+            link(&mut graph, "S", relay, None);
+            link(&mut graph, relay, "T", None);
+        }
+        assert_eq!(graph.max_flow("S", "T").unwrap().value, 3);
+
+        graph.set_node_availability("B", false).unwrap();
+        assert_eq!(graph.max_flow("S", "T").unwrap().value, 2);
+        assert!(graph.max_flow("S", "B").is_err());
+        assert!(graph.max_flow("S", "S").is_err());
+    }
+}
\ No newline at end of file
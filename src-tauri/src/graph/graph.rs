@@ -2,11 +2,14 @@ use std::cmp::Ordering;
 use std::collections::{BinaryHeap, HashMap, HashSet};
 use log::info;
 use crate::graph::edge::Edge;
-use crate::graph::node::{Node, NodeId};
+use crate::graph::node::{Behavior, Node, NodeId};
 
+pub type RouteMap = HashMap<(NodeId, NodeId), (Vec<NodeId>, u32)>;
+
+#[derive(Clone)]
 pub struct Graph {
     pub nodes: HashMap<NodeId, Node>,
-    edges: HashMap<(NodeId, NodeId), Edge>
+    pub edges: HashMap<(NodeId, NodeId), Edge>
 }
 
 impl Graph {
@@ -30,8 +33,12 @@ impl Graph {
         if self.edges.contains_key(&(source.clone(), target.clone())) {
             return Err("Edge already exists".to_string());
         }
-        self.nodes.insert(source.clone(), Node::new(source.clone()));
-        self.nodes.insert(target.clone(), Node::new(target.clone()));
+        // Existing endpoints keep their availability, compromise and behavior.
+        for id in [&source, &target] {
+            if !self.nodes.contains_key(id) {
+                self.nodes.insert(id.clone(), Node::new(id.clone()));
+            }
+        }
 
         let edge = Edge::new(source.clone(), target.clone(), cost);
         let reverse_edge = Edge::new(target.clone(), source.clone(), cost);
@@ -60,6 +67,37 @@ impl Graph {
         Err("Edge does not exist".to_string())
     }
 
+    pub fn set_edge_capacity(&mut self, source: &str, target: &str, capacity: Option<u32>) -> Result<(), String> {
+        if !self.edges.contains_key(&(source.to_string(), target.to_string())) {
+            return Err("Edge does not exist".to_string());
+        }
+
+        for key in [(source.to_string(), target.to_string()), (target.to_string(), source.to_string())] {
+            if let Some(edge) = self.edges.get_mut(&key) {
+                edge.capacity = capacity;
+            }
+        }
+
+        Ok(())
+    }
+
+    pub fn set_edge_loss(&mut self, source: &str, target: &str, loss: f64) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&loss) {
+            return Err("Loss probability must be between 0 and 1".to_string());
+        }
+        if !self.edges.contains_key(&(source.to_string(), target.to_string())) {
+            return Err("Edge does not exist".to_string());
+        }
+
+        for key in [(source.to_string(), target.to_string()), (target.to_string(), source.to_string())] {
+            if let Some(edge) = self.edges.get_mut(&key) {
+                edge.loss = loss;
+            }
+        }
+
+        Ok(())
+    }
+
     pub fn set_node_availability(&mut self, id: &str, status: bool) -> Result<(), String> {
         if let Some(node) = self.nodes.get_mut(id) {
             node.available = status;
@@ -69,6 +107,34 @@ impl Graph {
         Err("Node does not exist".to_string())
     }
 
+    pub fn set_node_compromise(&mut self, id: &str, compromise: f64) -> Result<(), String> {
+        if !(0.0..=1.0).contains(&compromise) {
+            return Err("Compromise probability must be between 0 and 1".to_string());
+        }
+
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.compromise = compromise;
+            return Ok(())
+        }
+
+        Err("Node does not exist".to_string())
+    }
+
+    pub fn set_node_behavior(&mut self, id: &str, behavior: Behavior) -> Result<(), String> {
+        if let Behavior::Grayhole { drop_rate } = behavior {
+            if !(0.0..=1.0).contains(&drop_rate) {
+                return Err("Drop rate must be between 0 and 1".to_string());
+            }
+        }
+
+        if let Some(node) = self.nodes.get_mut(id) {
+            node.behavior = behavior.clone();
+            return Ok(())
+        }
+
+        Err("Node does not exist".to_string())
+    }
+
     pub fn floyd_warshall(&self) -> (Vec<Vec<usize>>, Vec<Vec<usize>>) {
         let n = self.nodes.len();
 
@@ -102,39 +168,79 @@ impl Graph {
         (distances, predecessors)
     }
 
-    pub fn floyd_warshall_map(&self) -> HashMap<(NodeId, NodeId), (Vec<NodeId>, u32)> {
-        let nodes: Vec<NodeId> = self.nodes.keys().cloned().collect();
+    pub fn floyd_warshall_map(&self) -> RouteMap {
+        self.floyd_warshall_map_cancellable(|_, _| true).unwrap()
+    }
+
+    pub fn floyd_warshall_map_cancellable(&self, progress: impl FnMut(usize, usize) -> bool) -> Option<RouteMap> {
+        self.floyd_warshall_map_weighted(|_, _, cost| cost, progress)
+    }
+
+    pub fn floyd_warshall_map_weighted(&self, weight: impl Fn(&NodeId, &NodeId, u32) -> u32, mut progress: impl FnMut(usize, usize) -> bool) -> Option<RouteMap> {
+        let mut nodes: Vec<NodeId> = self.nodes.keys().cloned().collect();
+        nodes.sort();
         let mut dist: HashMap<(NodeId, NodeId), u32> = HashMap::new();
-        let mut next: HashMap<(NodeId, NodeId), NodeId> = HashMap::new();
 
         for i in &nodes {
             for j in &nodes {
                 if i == j {
                     dist.insert((i.clone(), j.clone()), 0);
                 } else if let Some(edge) = self.edges.get(&(i.clone(), j.clone())) {
-                    dist.insert((i.clone(), j.clone()), edge.cost);
-                    next.insert((i.clone(), j.clone()), j.clone());
+                    dist.insert((i.clone(), j.clone()), weight(i, j, edge.cost));
                 } else {
                     dist.insert((i.clone(), j.clone()), u32::MAX / 2);
                 }
             }
         }
 
-        for k in &nodes {
+        for (completed, k) in nodes.iter().enumerate() {
+            if !progress(completed, nodes.len()) {
+                return None;
+            }
+
             for i in &nodes {
                 for j in &nodes {
                     let ij = dist[&(i.clone(), j.clone())];
                     let ik = dist[&(i.clone(), k.clone())];
                     let kj = dist[&(k.clone(), j.clone())];
 
-                    if ik + kj < ij {
-                        dist.insert((i.clone(), j.clone()), ik + kj);
-                        next.insert((i.clone(), j.clone()), next[&(i.clone(), k.clone())].clone());
+                    let through = ik.saturating_add(kj);
+                    if through < ij {
+                        dist.insert((i.clone(), j.clone()), through);
                     }
                 }
             }
         }
 
+        // Next hops are picked after the distances settle so ties go to the smallest NodeId
+        // instead of whichever intermediate node happened to be relaxed first.
+        let mut neighbors: HashMap<&NodeId, Vec<(&NodeId, u32)>> = HashMap::new();
+        for ((source, target), edge) in &self.edges {
+            if source != target && self.nodes.contains_key(target) {
+                neighbors.entry(source).or_default().push((target, weight(source, target, edge.cost)));
+            }
+        }
+        for candidates in neighbors.values_mut() {
+            candidates.sort();
+        }
+
+        let mut next: HashMap<(NodeId, NodeId), NodeId> = HashMap::new();
+        for i in &nodes {
+            for j in &nodes {
+                let ij = dist[&(i.clone(), j.clone())];
+                if i == j || ij >= u32::MAX / 2 {
+                    continue;
+                }
+
+                let hop = neighbors.get(i).and_then(|candidates| {
+                    candidates.iter().find(|(n, cost)| cost.saturating_add(dist[&((*n).clone(), j.clone())]) == ij)
+                });
+                if let Some((n, _)) = hop {
+                    next.insert((i.clone(), j.clone()), (*n).clone());
+                }
+            }
+        }
+
         let mut paths: HashMap<(NodeId, NodeId), (Vec<NodeId>, u32)> = HashMap::new();
         for i in &nodes {
             for j in &nodes {
@@ -147,10 +253,10 @@ impl Graph {
             }
         }
 
-        paths
+        Some(paths)
     }
 
-    pub fn dijkstra(&mut self, start: Node) -> HashMap<NodeId, u32> {
+    pub fn dijkstra(&self, start: Node) -> HashMap<NodeId, u32> {
         let mut distances: HashMap<NodeId, u32> = HashMap::new();
         let mut visited: HashSet<NodeId> = HashSet::new();
         let mut priority_queue = BinaryHeap::new();
@@ -197,7 +303,7 @@ impl Graph {
         distances
     }
 
-    pub fn dijkstra_predecessors(&mut self, start: NodeId, target: NodeId) -> Option<(Vec<NodeId>, u32)> {
+    pub fn dijkstra_predecessors(&self, start: NodeId, target: NodeId) -> Option<(Vec<NodeId>, u32)> {
         let mut distances: HashMap<NodeId, u32> = HashMap::new();
         let mut predecessors: HashMap<NodeId, Option<NodeId>> = HashMap::new();
         let mut priority_queue = BinaryHeap::new();
@@ -245,7 +351,7 @@ impl Graph {
                     .filter(|edge| edge.source == current_node);
 
                 for edge in edges {
-                    if let Some(next_node) = self.nodes.get_mut(&edge.target.clone()) {
+                    if let Some(next_node) = self.nodes.get(&edge.target) {
                         if !next_node.available {
                             continue;
                         }
@@ -329,7 +435,37 @@ impl Graph {
         None
     }
 
-    fn is_node_available(&self, node_id: &NodeId) -> bool {
+    pub fn adjacency_list(&self) -> HashMap<NodeId, Vec<(NodeId, u32)>> {
+        let mut adjacency: HashMap<NodeId, Vec<(NodeId, u32)>> = self.nodes
+            .iter()
+            .filter(|(_, node)| node.available)
+            .map(|(id, _)| (id.clone(), Vec::new()))
+            .collect();
+
+        for edge in self.edges.values() {
+            if !adjacency.contains_key(&edge.target) {
+                continue;
+            }
+
+            if let Some(neighbors) = adjacency.get_mut(&edge.source) {
+                neighbors.push((edge.target.clone(), edge.cost));
+            }
+        }
+
+        adjacency
+    }
+
+    pub fn path_cost(&self, path: &[NodeId]) -> Option<u32> {
+        if path.iter().any(|node_id| !self.is_node_available(node_id)) {
+            return None;
+        }
+
+        path.windows(2)
+            .map(|hop| self.edges.get(&(hop[0].clone(), hop[1].clone())).map(|edge| edge.cost))
+            .sum()
+    }
+
+    pub(crate) fn is_node_available(&self, node_id: &NodeId) -> bool {
         self.nodes.get(node_id).map_or(false, |node| node.available)
     }
 
@@ -399,6 +535,10 @@ impl Graph {
         let mut current = i.clone();
 
         while current != *j {
+            if path.len() > self.nodes.len() {
+                return None;
+            }
+
             if let Some(next_node) = next.get(&(current.clone(), j.clone())) {
                 current = next_node.clone();
                 path.push(current.clone());
@@ -410,26 +550,47 @@ impl Graph {
         Some(path)
     }
 
-    pub fn get_fields(&mut self) -> (HashMap<NodeId, Node>, HashMap<(NodeId, NodeId), Edge>){
+    pub fn get_fields(&self) -> (HashMap<NodeId, Node>, HashMap<(NodeId, NodeId), Edge>){
         (self.nodes.clone(), self.edges.clone())
     }
 }
 
 #[derive(Eq, PartialEq)]
-struct State {
-    node: NodeId,
-    cost: u32
+pub(crate) struct State<N = NodeId> {
+    pub(crate) node: N,
+    pub(crate) cost: u32
 }
 
-impl Ord for State {
+impl<N: Eq> Ord for State<N> {
     fn cmp(&self, other: &Self) -> Ordering {
         other.cost.cmp(&self.cost)
     }
 }
 
-impl PartialOrd for State {
+impl<N: Eq> PartialOrd for State<N> {
     fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
         Some(self.cmp(other))
     }
 }
 
+#[cfg(test)]
+mod tests {
+    use crate::graph::node::Behavior;
+    use super::Graph;
+
+    #[test]
+    fn adding_an_edge_keeps_existing_node_state() {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph.set_node_availability("A", false).unwrap();
+        graph.set_node_compromise("A", 0.4).unwrap();
+        graph.set_node_behavior("A", Behavior::Blackhole).unwrap();
+
+        graph.add_edge("A".to_string(), "C".to_string(), 2).unwrap();
+
+        let node = &graph.nodes["A"];
+        assert!(!node.available);
+        assert_eq!(node.compromise, 0.4);
+        assert_eq!(node.behavior, Behavior::Blackhole);
+    }
+}
\ No newline at end of file
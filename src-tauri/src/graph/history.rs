@@ -0,0 +1,480 @@
+use serde::{Deserialize, Serialize};
+use crate::graph::edge::Edge;
+use crate::graph::event::GraphEvent;
+use crate::graph::graph::Graph;
+use crate::graph::node::{Behavior, Node, NodeId};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "op", rename_all = "snake_case")]
+pub enum EditOperation {
+    AddNode { id: NodeId },
+    RemoveNode { id: NodeId },
+    AddEdge { source: NodeId, target: NodeId, cost: u32 },
+    RemoveEdge { source: NodeId, target: NodeId },
+    SetNodeAvailability { id: NodeId, available: bool },
+    SetNodeCompromise { id: NodeId, compromise: f64 },
+    SetNodeBehavior { id: NodeId, behavior: Behavior },
+    SetEdgeCapacity { source: NodeId, target: NodeId, capacity: Option<u32> },
+    SetEdgeLoss { source: NodeId, target: NodeId, loss: f64 },
+}
+
+#[derive(Clone, Debug)]
+enum Entry {
+    Node(NodeId, Option<Node>),
+    Edge((NodeId, NodeId), Option<Edge>),
+}
+
+#[derive(Clone, Debug)]
+struct Step {
+    operation: EditOperation,
+    previous: Vec<Entry>,
+}
+
+// `events` holds what the edits made through the journal changed, until the caller takes them.
+#[derive(Clone, Debug, Default)]
+pub struct History {
+    undo_stack: Vec<Vec<Step>>,
+    redo_stack: Vec<Vec<Step>>,
+    open: Option<Vec<Step>>,
+    events: Vec<GraphEvent>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum OperationStatus {
+    Applied,
+    Failed { error: String },
+    RolledBack,
+    Skipped,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct OperationResult {
+    pub index: usize,
+    pub operation: EditOperation,
+    #[serde(flatten)]
+    pub status: OperationStatus,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct BatchResult {
+    pub committed: bool,
+    pub results: Vec<OperationResult>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HistoryStatus {
+    pub can_undo: bool,
+    pub can_redo: bool,
+    pub in_transaction: bool,
+}
+
+// The undo journal is kept apart from the graph it edits, so copies of the graph made for what-if
+// analysis don't carry it along.
+impl History {
+    pub fn status(&self) -> HistoryStatus {
+        HistoryStatus {
+            can_undo: !self.undo_stack.is_empty(),
+            can_redo: !self.redo_stack.is_empty(),
+            in_transaction: self.open.is_some(),
+        }
+    }
+
+    pub fn take_events(&mut self) -> Vec<GraphEvent> {
+        std::mem::take(&mut self.events)
+    }
+
+    pub fn apply(&mut self, graph: &mut Graph, operation: &EditOperation) -> Result<(), String> {
+        let step = graph.execute(operation, &mut self.events)?;
+
+        match &mut self.open {
+            Some(steps) => steps.push(step),
+            None => self.undo_stack.push(vec![step]),
+        }
+        self.redo_stack.clear();
+
+        Ok(())
+    }
+
+    pub fn apply_batch(&mut self, graph: &mut Graph, operations: &[EditOperation]) -> BatchResult {
+        let checkpoint = self.events.len();
+        let mut steps = Vec::new();
+        let mut failure = None;
+
+        for (index, operation) in operations.iter().enumerate() {
+            match graph.execute(operation, &mut self.events) {
+                Ok(step) => steps.push(step),
+                Err(err) => {
+                    failure = Some((index, err));
+                    break;
+                }
+            }
+        }
+
+        if let Some((failed_index, error)) = failure {
+            graph.revert(&steps, &mut self.events);
+            self.events.truncate(checkpoint);
+
+            let results = operations
+                .iter()
+                .enumerate()
+                .map(|(index, operation)| {
+                    let status = if index < failed_index {
+                        OperationStatus::RolledBack
+                    } else if index == failed_index {
+                        OperationStatus::Failed { error: error.clone() }
+                    } else {
+                        OperationStatus::Skipped
+                    };
+                    OperationResult { index, operation: operation.clone(), status }
+                })
+                .collect();
+
+            return BatchResult { committed: false, results };
+        }
+
+        if !steps.is_empty() {
+            match &mut self.open {
+                Some(open) => open.extend(steps),
+                None => self.undo_stack.push(steps),
+            }
+            self.redo_stack.clear();
+        }
+
+        let results = operations
+            .iter()
+            .enumerate()
+            .map(|(index, operation)| OperationResult { index, operation: operation.clone(), status: OperationStatus::Applied })
+            .collect();
+
+        BatchResult { committed: true, results }
+    }
+
+    pub fn begin_transaction(&mut self) -> Result<(), String> {
+        if self.open.is_some() {
+            return Err("A transaction is already open".to_string());
+        }
+
+        self.open = Some(Vec::new());
+        Ok(())
+    }
+
+    pub fn commit_transaction(&mut self) -> Result<(), String> {
+        match self.open.take() {
+            Some(steps) => {
+                if !steps.is_empty() {
+                    self.undo_stack.push(steps);
+                }
+                Ok(())
+            }
+            None => Err("No transaction is open".to_string()),
+        }
+    }
+
+    // Returns whether the transaction had edited the graph.
+    pub fn rollback_transaction(&mut self, graph: &mut Graph) -> Result<bool, String> {
+        match self.open.take() {
+            Some(steps) => {
+                graph.revert(&steps, &mut self.events);
+                Ok(!steps.is_empty())
+            }
+            None => Err("No transaction is open".to_string()),
+        }
+    }
+
+    pub fn undo(&mut self, graph: &mut Graph) -> Result<bool, String> {
+        if self.open.is_some() {
+            return Err("Cannot undo while a transaction is open".to_string());
+        }
+
+        match self.undo_stack.pop() {
+            Some(steps) => {
+                graph.revert(&steps, &mut self.events);
+                self.redo_stack.push(steps);
+                Ok(true)
+            }
+            None => Ok(false),
+        }
+    }
+
+    pub fn redo(&mut self, graph: &mut Graph) -> Result<bool, String> {
+        if self.open.is_some() {
+            return Err("Cannot redo while a transaction is open".to_string());
+        }
+
+        let steps = match self.redo_stack.pop() {
+            Some(steps) => steps,
+            None => return Ok(false),
+        };
+
+        let checkpoint = self.events.len();
+        let mut replayed = Vec::new();
+        for step in &steps {
+            match graph.execute(&step.operation, &mut self.events) {
+                Ok(step) => replayed.push(step),
+                Err(err) => {
+                    graph.revert(&replayed, &mut self.events);
+                    self.events.truncate(checkpoint);
+                    self.redo_stack.push(steps);
+                    return Err(err);
+                }
+            }
+        }
+        self.undo_stack.push(replayed);
+
+        Ok(true)
+    }
+}
+
+impl Graph {
+    fn execute(&mut self, operation: &EditOperation, events: &mut Vec<GraphEvent>) -> Result<Step, String> {
+        let previous = self.touched_entries(operation);
+
+        match operation {
+            EditOperation::AddNode { id } => self.add_node(id),
+            EditOperation::RemoveNode { id } => self.remove_node(id),
+            EditOperation::AddEdge { source, target, cost } => self.add_edge(source.clone(), target.clone(), *cost),
+            EditOperation::RemoveEdge { source, target } => self.remove_edge(source.clone(), target.clone()),
+            EditOperation::SetNodeAvailability { id, available } => self.set_node_availability(id, *available),
+            EditOperation::SetNodeCompromise { id, compromise } => self.set_node_compromise(id, *compromise),
+            EditOperation::SetNodeBehavior { id, behavior } => self.set_node_behavior(id, behavior.clone()),
+            EditOperation::SetEdgeCapacity { source, target, capacity } => self.set_edge_capacity(source, target, *capacity),
+            EditOperation::SetEdgeLoss { source, target, loss } => self.set_edge_loss(source, target, *loss),
+        }?;
+
+        events.extend(operation_events(operation, &previous));
+        Ok(Step { operation: operation.clone(), previous })
+    }
+
+    fn touched_entries(&self, operation: &EditOperation) -> Vec<Entry> {
+        let node = |id: &NodeId| Entry::Node(id.clone(), self.nodes.get(id).cloned());
+        let edge = |source: &NodeId, target: &NodeId| {
+            let key = (source.clone(), target.clone());
+            Entry::Edge(key.clone(), self.edges.get(&key).cloned())
+        };
+
+        match operation {
+            EditOperation::AddNode { id }
+            | EditOperation::RemoveNode { id }
+            | EditOperation::SetNodeAvailability { id, .. }
+            | EditOperation::SetNodeCompromise { id, .. }
+            | EditOperation::SetNodeBehavior { id, .. } => vec![node(id)],
+
+            EditOperation::AddEdge { source, target, .. } => vec![
+                node(source),
+                node(target),
+                edge(source, target),
+                edge(target, source),
+            ],
+            EditOperation::RemoveEdge { source, target } => vec![edge(source, target)],
+            EditOperation::SetEdgeCapacity { source, target, .. }
+            | EditOperation::SetEdgeLoss { source, target, .. } => vec![edge(source, target), edge(target, source)],
+        }
+    }
+
+    fn revert(&mut self, steps: &[Step], events: &mut Vec<GraphEvent>) {
+        for step in steps.iter().rev() {
+            for entry in step.previous.iter().rev() {
+                self.restore(entry, events);
+            }
+        }
+    }
+
+    fn restore(&mut self, entry: &Entry, events: &mut Vec<GraphEvent>) {
+        match entry {
+            Entry::Node(id, previous) => {
+                let current = match previous {
+                    Some(node) => self.nodes.insert(id.clone(), node.clone()),
+                    None => self.nodes.remove(id),
+                };
+
+                match (current, previous) {
+                    (Some(_), None) => events.push(GraphEvent::NodeRemoved { id: id.clone() }),
+                    (None, Some(node)) => {
+                        events.push(GraphEvent::NodeAdded { id: id.clone() });
+                        if !node.available {
+                            events.push(GraphEvent::AvailabilityChanged { id: id.clone(), available: false });
+                        }
+                        if node.compromise > 0.0 {
+                            events.push(GraphEvent::CompromiseChanged { id: id.clone(), compromise: node.compromise });
+                        }
+                        if node.behavior != Behavior::Honest {
+                            events.push(GraphEvent::BehaviorChanged { id: id.clone(), behavior: node.behavior.clone() });
+                        }
+                    }
+                    (Some(current), Some(node)) => {
+                        if current.available != node.available {
+                            events.push(GraphEvent::AvailabilityChanged { id: id.clone(), available: node.available });
+                        }
+                        if current.compromise != node.compromise {
+                            events.push(GraphEvent::CompromiseChanged { id: id.clone(), compromise: node.compromise });
+                        }
+                        if current.behavior != node.behavior {
+                            events.push(GraphEvent::BehaviorChanged { id: id.clone(), behavior: node.behavior.clone() });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+            Entry::Edge((source, target), previous) => {
+                let key = (source.clone(), target.clone());
+                let current = match previous {
+                    Some(edge) => self.edges.insert(key, edge.clone()),
+                    None => self.edges.remove(&key),
+                };
+
+                match (current, previous) {
+                    (Some(_), None) => events.push(GraphEvent::EdgeRemoved { source: source.clone(), target: target.clone() }),
+                    (None, Some(edge)) => events.push(GraphEvent::EdgeAdded { source: source.clone(), target: target.clone(), cost: edge.cost }),
+                    (Some(current), Some(edge)) => {
+                        if current.capacity != edge.capacity {
+                            events.push(GraphEvent::CapacityChanged { source: source.clone(), target: target.clone(), capacity: edge.capacity });
+                        }
+                        if current.loss != edge.loss {
+                            events.push(GraphEvent::LossChanged { source: source.clone(), target: target.clone(), loss: edge.loss });
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+fn operation_events(operation: &EditOperation, previous: &[Entry]) -> Vec<GraphEvent> {
+    match operation.clone() {
+        EditOperation::AddNode { id } => vec![GraphEvent::NodeAdded { id }],
+        EditOperation::RemoveNode { id } => vec![GraphEvent::NodeRemoved { id }],
+        EditOperation::AddEdge { source, target, cost } => {
+            let mut events = Vec::new();
+            for entry in previous {
+                if let Entry::Node(id, None) = entry {
+                    let event = GraphEvent::NodeAdded { id: id.clone() };
+                    if !events.contains(&event) {
+                        events.push(event);
+                    }
+                }
+            }
+            events.push(GraphEvent::EdgeAdded { source, target, cost });
+            events
+        }
+        EditOperation::RemoveEdge { source, target } => vec![GraphEvent::EdgeRemoved { source, target }],
+        EditOperation::SetNodeAvailability { id, available } => vec![GraphEvent::AvailabilityChanged { id, available }],
+        EditOperation::SetNodeCompromise { id, compromise } => vec![GraphEvent::CompromiseChanged { id, compromise }],
+        EditOperation::SetNodeBehavior { id, behavior } => vec![GraphEvent::BehaviorChanged { id, behavior }],
+        EditOperation::SetEdgeCapacity { source, target, capacity } => vec![GraphEvent::CapacityChanged { source, target, capacity }],
+        EditOperation::SetEdgeLoss { source, target, loss } => vec![GraphEvent::LossChanged { source, target, loss }],
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::event::GraphEvent;
+    use crate::graph::graph::Graph;
+    use super::{EditOperation, History, OperationStatus};
+
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph
+    }
+
+    fn add_edge(source: &str, target: &str, cost: u32) -> EditOperation {
+        EditOperation::AddEdge { source: source.to_string(), target: target.to_string(), cost }
+    }
+
+    #[test]
+    fn undo_and_redo_restore_each_edit() {
+        let mut graph = graph();
+        let mut history = History::default();
+        history.apply(&mut graph, &EditOperation::SetNodeAvailability { id: "A".to_string(), available: false }).unwrap();
+        history.apply(&mut graph, &add_edge("A", "C", 3)).unwrap();
+
+        assert!(history.undo(&mut graph).unwrap());
+        assert!(!graph.nodes.contains_key("C"));
+        assert_eq!(graph.edges.len(), 2);
+        assert!(!graph.nodes["A"].available);
+
+        assert!(history.undo(&mut graph).unwrap());
+        assert!(graph.nodes["A"].available);
+        assert!(!history.undo(&mut graph).unwrap());
+
+        assert!(history.redo(&mut graph).unwrap());
+        assert!(history.redo(&mut graph).unwrap());
+        assert!(!graph.nodes["A"].available);
+        assert_eq!(graph.edges.len(), 4);
+        assert!(!history.redo(&mut graph).unwrap());
+
+        history.undo(&mut graph).unwrap();
+        history.apply(&mut graph, &add_edge("B", "D", 2)).unwrap();
+        assert!(!history.status().can_redo);
+    }
+
+    #[test]
+    fn transactions_undo_as_one_step_or_roll_back() {
+        let mut graph = graph();
+        let mut history = History::default();
+
+        history.begin_transaction().unwrap();
+        assert!(history.begin_transaction().is_err());
+        history.apply(&mut graph, &add_edge("A", "C", 3)).unwrap();
+        history.apply(&mut graph, &EditOperation::SetEdgeCapacity { source: "A".to_string(), target: "C".to_string(), capacity: Some(4) }).unwrap();
+        assert!(history.undo(&mut graph).is_err());
+        history.commit_transaction().unwrap();
+
+        assert!(history.undo(&mut graph).unwrap());
+        assert!(!graph.nodes.contains_key("C"));
+        assert!(!history.status().can_undo);
+
+        history.begin_transaction().unwrap();
+        history.apply(&mut graph, &EditOperation::RemoveNode { id: "B".to_string() }).unwrap();
+        assert!(history.rollback_transaction(&mut graph).unwrap());
+        assert!(graph.nodes.contains_key("B"));
+        assert_eq!(graph.edges.len(), 2);
+        assert!(!history.status().in_transaction);
+        assert!(!history.status().can_undo);
+        assert!(history.rollback_transaction(&mut graph).is_err());
+    }
+
+    #[test]
+    fn edits_report_what_they_changed() {
+        let mut graph = graph();
+        let mut history = History::default();
+
+        history.apply(&mut graph, &add_edge("A", "C", 2)).unwrap();
+        assert_eq!(history.take_events(), vec![
+            GraphEvent::NodeAdded { id: "C".to_string() },
+            GraphEvent::EdgeAdded { source: "A".to_string(), target: "C".to_string(), cost: 2 },
+        ]);
+
+        history.undo(&mut graph).unwrap();
+        let events = history.take_events();
+        assert!(events.contains(&GraphEvent::NodeRemoved { id: "C".to_string() }));
+        assert!(events.contains(&GraphEvent::EdgeRemoved { source: "A".to_string(), target: "C".to_string() }));
+        assert!(!events.iter().any(|event| matches!(event, GraphEvent::NodeRemoved { id } if id == "A")));
+
+        assert!(history.apply(&mut graph, &EditOperation::RemoveNode { id: "Z".to_string() }).is_err());
+        assert!(history.take_events().is_empty());
+    }
+
+    #[test]
+    fn failed_batches_leave_the_graph_untouched() {
+        let mut graph = graph();
+        let mut history = History::default();
+
+        let result = history.apply_batch(&mut graph, &[
+            add_edge("B", "C", 1),
+            EditOperation::RemoveNode { id: "Z".to_string() },
+            add_edge("C", "D", 1),
+        ]);
+
+        assert!(!result.committed);
+        let statuses: Vec<_> = result.results.into_iter().map(|result| result.status).collect();
+        assert_eq!(statuses[0], OperationStatus::RolledBack);
+        assert!(matches!(statuses[1], OperationStatus::Failed { .. }));
+        assert_eq!(statuses[2], OperationStatus::Skipped);
+        assert!(!graph.nodes.contains_key("C"));
+        assert!(history.take_events().is_empty());
+        assert!(!history.status().can_undo);
+    }
+}
\ No newline at end of file
@@ -1,3 +1,15 @@
+pub mod algorithm;
+pub mod all_pairs;
+pub mod centrality;
+pub mod circuit;
+pub mod ecmp;
 pub mod edge;
+pub mod event;
+pub mod failure;
+pub mod flow;
 pub mod graph;
-pub mod node;
\ No newline at end of file
+pub mod history;
+pub mod node;
+pub mod route_table;
+pub mod security;
+pub mod snapshot;
\ No newline at end of file
@@ -1,13 +1,36 @@
+use serde::{Deserialize, Serialize};
+
 pub type NodeId = String;
 
-#[derive(Clone, Debug)]
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
 pub struct Node {
     pub id: NodeId,
     pub available: bool,
+    #[serde(default)]
+    pub compromise: f64,
+    #[serde(default)]
+    pub behavior: Behavior,
+}
+
+// How a node treats traffic it forwards while still reporting itself as available.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum Behavior {
+    Honest,
+    Blackhole,
+    Grayhole { drop_rate: f64 },
+    Modifier,
+    Delayer { delay: u32 },
+}
+
+impl Default for Behavior {
+    fn default() -> Self {
+        Behavior::Honest
+    }
 }
 
 impl Node {
     pub fn new(id: NodeId) -> Self {
-        Self { id , available: true }
+        Self { id , available: true, compromise: 0.0, behavior: Behavior::Honest }
     }
 }
\ No newline at end of file
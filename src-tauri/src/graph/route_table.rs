@@ -0,0 +1,166 @@
+use std::collections::HashMap;
+use std::mem::size_of;
+use serde::Serialize;
+use crate::graph::node::NodeId;
+
+const NO_ROUTE: u32 = u32::MAX;
+
+// Distance and next-hop matrices over interned node indices; paths are rebuilt by following
+// next hops instead of being stored per pair.
+#[derive(Clone, Debug, Default)]
+pub struct RouteTable {
+    nodes: Vec<NodeId>,
+    index: HashMap<NodeId, u32>,
+    distances: Vec<u32>,
+    next_hops: Vec<u32>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RouteMemoryStats {
+    pub nodes: usize,
+    pub routes: usize,
+    pub table_bytes: usize,
+    pub path_map_bytes: usize,
+}
+
+impl RouteTable {
+    pub fn new(nodes: Vec<NodeId>) -> RouteTable {
+        let n = nodes.len();
+        let index = nodes.iter().enumerate().map(|(i, id)| (id.clone(), i as u32)).collect();
+
+        let mut table = RouteTable {
+            nodes,
+            index,
+            distances: vec![NO_ROUTE; n * n],
+            next_hops: vec![NO_ROUTE; n * n],
+        };
+        for i in 0..n {
+            table.distances[i * n + i] = 0;
+        }
+
+        table
+    }
+
+    // Each column holds the distances and next hops of every source towards one target.
+    pub fn from_columns(nodes: Vec<NodeId>, columns: Vec<(Vec<u32>, Vec<u32>)>) -> RouteTable {
+        let mut table = RouteTable::new(nodes);
+        let n = table.nodes.len();
+
+        for (target, (distance_column, next_hop_column)) in columns.into_iter().enumerate() {
+            for (source, (distance, next_hop)) in distance_column.into_iter().zip(next_hop_column).enumerate() {
+                table.distances[source * n + target] = distance;
+                table.next_hops[source * n + target] = next_hop;
+            }
+        }
+
+        table
+    }
+
+    pub fn from_paths(routes: HashMap<(NodeId, NodeId), (Vec<NodeId>, u32)>) -> RouteTable {
+        let mut nodes: Vec<NodeId> = routes.keys().flat_map(|(source, target)| [source.clone(), target.clone()]).collect();
+        nodes.sort();
+        nodes.dedup();
+
+        let mut table = RouteTable::new(nodes);
+        for ((source, target), (path, cost)) in routes {
+            if let (Some(&s), Some(&t), Some(next)) = (table.index.get(&source), table.index.get(&target), path.get(1)) {
+                let slot = table.slot(s, t);
+                table.distances[slot] = cost;
+                table.next_hops[slot] = table.index[next];
+            }
+        }
+
+        table
+    }
+
+    pub fn nodes(&self) -> &[NodeId] {
+        &self.nodes
+    }
+
+    pub fn len(&self) -> usize {
+        self.next_hops.iter().filter(|&&hop| hop != NO_ROUTE).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub fn distance(&self, source: &str, target: &str) -> Option<u32> {
+        let (s, t) = self.indices(source, target)?;
+        match self.distances[self.slot(s, t)] {
+            NO_ROUTE => None,
+            distance => Some(distance),
+        }
+    }
+
+    pub fn next_hop(&self, source: &str, target: &str) -> Option<&NodeId> {
+        let (s, t) = self.indices(source, target)?;
+        match self.next_hops[self.slot(s, t)] {
+            NO_ROUTE => None,
+            hop => Some(&self.nodes[hop as usize]),
+        }
+    }
+
+    pub fn path(&self, source: &str, target: &str) -> Option<(Vec<NodeId>, u32)> {
+        let (s, t) = self.indices(source, target)?;
+        if s == t || self.next_hops[self.slot(s, t)] == NO_ROUTE {
+            return None;
+        }
+
+        let mut path = vec![self.nodes[s as usize].clone()];
+        let mut current = s;
+        while current != t {
+            current = self.next_hops[self.slot(current, t)];
+            if current == NO_ROUTE || path.len() > self.nodes.len() {
+                return None;
+            }
+            path.push(self.nodes[current as usize].clone());
+        }
+
+        Some((path, self.distances[self.slot(s, t)]))
+    }
+
+    pub fn to_paths(&self) -> HashMap<(NodeId, NodeId), (Vec<NodeId>, u32)> {
+        let mut routes = HashMap::new();
+        for source in &self.nodes {
+            for target in &self.nodes {
+                if let Some(route) = self.path(source, target) {
+                    routes.insert((source.clone(), target.clone()), route);
+                }
+            }
+        }
+
+        routes
+    }
+
+    pub fn memory_usage(&self) -> RouteMemoryStats {
+        let id_bytes = |id: &NodeId| size_of::<NodeId>() + id.capacity();
+
+        let table_bytes = self.nodes.iter().map(id_bytes).sum::<usize>()
+            + self.index.keys().map(|id| id_bytes(id) + size_of::<u32>()).sum::<usize>()
+            + (self.distances.capacity() + self.next_hops.capacity()) * size_of::<u32>();
+
+        let mut routes = 0;
+        let mut path_map_bytes = 0;
+        for source in &self.nodes {
+            for target in &self.nodes {
+                if let Some((path, _)) = self.path(source, target) {
+                    routes += 1;
+                    path_map_bytes += id_bytes(source) + id_bytes(target)
+                        + size_of::<(Vec<NodeId>, u32)>()
+                        + path.iter().map(id_bytes).sum::<usize>();
+                }
+            }
+        }
+
+        RouteMemoryStats { nodes: self.nodes.len(), routes, table_bytes, path_map_bytes }
+    }
+
+    fn indices(&self, source: &str, target: &str) -> Option<(u32, u32)> {
+        Some((*self.index.get(source)?, *self.index.get(target)?))
+    }
+
+    fn slot(&self, source: u32, target: u32) -> usize {
+        source as usize * self.nodes.len() + target as usize
+    }
+}
\ No newline at end of file
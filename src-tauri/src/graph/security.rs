@@ -0,0 +1,209 @@
+use std::cmp::Ordering;
+use std::collections::{BinaryHeap, HashMap};
+use serde::Serialize;
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+pub struct Adversary {
+    pub id: NodeId,
+    pub compromise: f64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PathSecurity {
+    pub path: Vec<NodeId>,
+    pub cost: u32,
+    pub observation_probability: f64,
+    pub adversaries: Vec<Adversary>,
+}
+
+// Known-compromised nodes are counted apart from the log-probability sum so that any path
+// avoiding them beats one that crosses them; cost only breaks ties.
+#[derive(Clone, Copy, PartialEq, PartialOrd)]
+struct Exposure {
+    certain: u32,
+    risk: f64,
+    cost: u32,
+}
+
+impl Exposure {
+    fn of(compromise: f64) -> Exposure {
+        if compromise >= 1.0 {
+            Exposure { certain: 1, risk: 0.0, cost: 0 }
+        } else {
+            Exposure { certain: 0, risk: -(-compromise).ln_1p(), cost: 0 }
+        }
+    }
+
+    fn add(self, other: Exposure, cost: u32) -> Exposure {
+        Exposure {
+            certain: self.certain + other.certain,
+            risk: self.risk + other.risk,
+            cost: self.cost + other.cost + cost,
+        }
+    }
+}
+
+#[derive(PartialEq)]
+struct Candidate {
+    exposure: Exposure,
+    node: NodeId,
+}
+
+impl Eq for Candidate {}
+
+impl Ord for Candidate {
+    fn cmp(&self, other: &Self) -> Ordering {
+        other.exposure.partial_cmp(&self.exposure).unwrap_or(Ordering::Equal)
+    }
+}
+
+impl PartialOrd for Candidate {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Graph {
+    // Nodes are compromised independently, so a path stays unobserved only if every node on it,
+    // endpoints included, is honest.
+    pub fn evaluate_path_security(&self, path: &[NodeId]) -> Result<PathSecurity, String> {
+        if path.is_empty() {
+            return Err("Path is empty".to_string());
+        }
+
+        let mut adversaries = Vec::new();
+        let mut unobserved = 1.0;
+        for id in path {
+            let node = self.nodes.get(id).ok_or(format!("Node {} does not exist", id))?;
+            if !node.available {
+                return Err(format!("Node {} is not available", id));
+            }
+
+            if node.compromise > 0.0 && !adversaries.iter().any(|adversary: &Adversary| &adversary.id == id) {
+                adversaries.push(Adversary { id: id.clone(), compromise: node.compromise });
+                unobserved *= 1.0 - node.compromise;
+            }
+        }
+
+        let mut cost = 0;
+        for hop in path.windows(2) {
+            let edge = self.edges
+                .get(&(hop[0].clone(), hop[1].clone()))
+                .ok_or(format!("Edge {} -> {} does not exist", hop[0], hop[1]))?;
+            cost += edge.cost;
+        }
+
+        Ok(PathSecurity { path: path.to_vec(), cost, observation_probability: 1.0 - unobserved, adversaries })
+    }
+
+    // Dijkstra over -ln(1 - p) per node, which maximizes the probability that no node on the path
+    // is compromised.
+    pub fn most_secure_path(&self, source: &str, target: &str) -> Option<PathSecurity> {
+        let adjacency = self.adjacency_list();
+        let exposure = |id: &NodeId| Exposure::of(self.nodes[id].compromise);
+
+        let start = exposure(adjacency.get_key_value(source)?.0);
+        let mut best: HashMap<NodeId, Exposure> = HashMap::new();
+        let mut predecessors: HashMap<NodeId, NodeId> = HashMap::new();
+        let mut queue = BinaryHeap::new();
+
+        best.insert(source.to_string(), start);
+        queue.push(Candidate { exposure: start, node: source.to_string() });
+
+        while let Some(Candidate { exposure: current_exposure, node: current }) = queue.pop() {
+            if best.get(&current).map_or(false, |known| current_exposure > *known) {
+                continue;
+            }
+
+            if current == target {
+                let mut path = vec![current.clone()];
+                while let Some(previous) = predecessors.get(path.last().unwrap()) {
+                    path.push(previous.clone());
+                }
+                path.reverse();
+                return self.evaluate_path_security(&path).ok();
+            }
+
+            for (next, cost) in &adjacency[&current] {
+                let candidate = current_exposure.add(exposure(next), *cost);
+                if best.get(next).map_or(true, |known| candidate < *known) {
+                    best.insert(next.clone(), candidate);
+                    predecessors.insert(next.clone(), current.clone());
+                    queue.push(Candidate { exposure: candidate, node: next.clone() });
+                }
+            }
+        }
+
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+    use super::Adversary;
+
+    // Three relays between A and D: B and C on cheap links, E on expensive ones.
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        for (relay, cost) in [("B", 1), ("C", 1), ("E", 5)] {
+            graph.add_edge("A".to_string(), relay.to_string(), cost).unwrap();
+            graph.add_edge(relay.to_string(), "D".to_string(), cost).unwrap();
+        }
+        graph
+    }
+
+    fn path(graph: &Graph) -> Vec<String> {
+        graph.most_secure_path("A", "D").unwrap().path
+    }
+
+    #[test]
+    fn certain_compromise_outweighs_risk_and_risk_outweighs_cost() {
+        let mut graph = graph();
+        graph.set_node_compromise("B", 1.0).unwrap();
+        graph.set_node_compromise("C", 0.9).unwrap();
+        graph.set_node_compromise("E", 0.95).unwrap();
+        assert_eq!(path(&graph), vec!["A", "C", "D"]);
+
+        graph.set_node_compromise("E", 0.1).unwrap();
+        assert_eq!(path(&graph), vec!["A", "E", "D"]);
+
+        graph.set_node_compromise("C", 0.1).unwrap();
+        assert_eq!(path(&graph), vec!["A", "C", "D"]);
+    }
+
+    #[test]
+    fn unavailable_nodes_are_never_used() {
+        let mut graph = graph();
+        graph.set_node_compromise("B", 0.5).unwrap();
+        graph.set_node_compromise("C", 0.6).unwrap();
+        graph.set_node_availability("E", false).unwrap();
+        assert_eq!(path(&graph), vec!["A", "B", "D"]);
+
+        graph.set_node_availability("B", false).unwrap();
+        graph.set_node_availability("C", false).unwrap();
+        assert!(graph.most_secure_path("A", "D").is_none());
+        assert!(graph.evaluate_path_security(&["A".to_string(), "E".to_string(), "D".to_string()]).is_err());
+    }
+
+    #[test]
+    fn observation_counts_every_compromised_node_once() {
+        let mut graph = graph();
+        graph.set_node_compromise("A", 0.2).unwrap();
+        graph.set_node_compromise("C", 0.5).unwrap();
+
+        let path: Vec<String> = ["A", "C", "D", "C"].iter().map(|id| id.to_string()).collect();
+        let security = graph.evaluate_path_security(&path).unwrap();
+        assert_eq!(security.cost, 3);
+        assert!((security.observation_probability - 0.6).abs() < 1e-9);
+        assert_eq!(security.adversaries, vec![
+            Adversary { id: "A".to_string(), compromise: 0.2 },
+            Adversary { id: "C".to_string(), compromise: 0.5 },
+        ]);
+
+        assert!(graph.evaluate_path_security(&["B".to_string(), "C".to_string()]).is_err());
+        assert!(graph.evaluate_path_security(&[]).is_err());
+    }
+}
\ No newline at end of file
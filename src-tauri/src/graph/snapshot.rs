@@ -0,0 +1,280 @@
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+use log::error;
+use serde::{Deserialize, Serialize};
+use crate::graph::edge::Edge;
+use crate::graph::graph::Graph;
+use crate::graph::node::{Node, NodeId};
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Snapshot {
+    pub name: String,
+    pub created_at: u64,
+    pub nodes: Vec<Node>,
+    pub edges: Vec<Edge>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SnapshotSummary {
+    pub name: String,
+    pub created_at: u64,
+    pub nodes: usize,
+    pub edges: usize,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct CostChange {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub before: u32,
+    pub after: u32,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct AvailabilityChange {
+    pub id: NodeId,
+    pub before: bool,
+    pub after: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct RouteChange {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub before: Option<(Vec<NodeId>, u32)>,
+    pub after: Option<(Vec<NodeId>, u32)>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SnapshotDiff {
+    pub before: String,
+    pub after: String,
+    pub added_nodes: Vec<NodeId>,
+    pub removed_nodes: Vec<NodeId>,
+    pub added_edges: Vec<(NodeId, NodeId)>,
+    pub removed_edges: Vec<(NodeId, NodeId)>,
+    pub cost_changes: Vec<CostChange>,
+    pub availability_changes: Vec<AvailabilityChange>,
+    pub route_changes: Vec<RouteChange>,
+}
+
+impl Snapshot {
+    pub fn to_graph(&self) -> Graph {
+        let mut graph = Graph::new();
+        graph.nodes = self.nodes.iter().map(|node| (node.id.clone(), node.clone())).collect();
+        graph.edges = self.edges.iter().map(|edge| ((edge.source.clone(), edge.target.clone()), edge.clone())).collect();
+        graph
+    }
+
+    pub fn summary(&self) -> SnapshotSummary {
+        SnapshotSummary {
+            name: self.name.clone(),
+            created_at: self.created_at,
+            nodes: self.nodes.len(),
+            edges: self.edges.len(),
+        }
+    }
+
+    pub fn diff(&self, after: &Snapshot) -> SnapshotDiff {
+        let before_nodes: BTreeMap<&NodeId, &Node> = self.nodes.iter().map(|node| (&node.id, node)).collect();
+        let after_nodes: BTreeMap<&NodeId, &Node> = after.nodes.iter().map(|node| (&node.id, node)).collect();
+        let before_edges: BTreeMap<(&NodeId, &NodeId), &Edge> = self.edges.iter().map(|edge| ((&edge.source, &edge.target), edge)).collect();
+        let after_edges: BTreeMap<(&NodeId, &NodeId), &Edge> = after.edges.iter().map(|edge| ((&edge.source, &edge.target), edge)).collect();
+
+        let added_nodes = after_nodes.keys().filter(|id| !before_nodes.contains_key(*id)).map(|id| (*id).clone()).collect();
+        let removed_nodes = before_nodes.keys().filter(|id| !after_nodes.contains_key(*id)).map(|id| (*id).clone()).collect();
+
+        let availability_changes = before_nodes
+            .iter()
+            .filter_map(|(id, node)| {
+                let after_node = after_nodes.get(id)?;
+                if node.available == after_node.available {
+                    return None;
+                }
+                Some(AvailabilityChange { id: (*id).clone(), before: node.available, after: after_node.available })
+            })
+            .collect();
+
+        let added_edges = after_edges.keys()
+            .filter(|key| !before_edges.contains_key(*key))
+            .map(|(source, target)| ((*source).clone(), (*target).clone()))
+            .collect();
+        let removed_edges = before_edges.keys()
+            .filter(|key| !after_edges.contains_key(*key))
+            .map(|(source, target)| ((*source).clone(), (*target).clone()))
+            .collect();
+
+        let cost_changes = before_edges
+            .iter()
+            .filter_map(|(key, edge)| {
+                let after_edge = after_edges.get(key)?;
+                if edge.cost == after_edge.cost {
+                    return None;
+                }
+                Some(CostChange { source: edge.source.clone(), target: edge.target.clone(), before: edge.cost, after: after_edge.cost })
+            })
+            .collect();
+
+        SnapshotDiff {
+            before: self.name.clone(),
+            after: after.name.clone(),
+            added_nodes,
+            removed_nodes,
+            added_edges,
+            removed_edges,
+            cost_changes,
+            availability_changes,
+            route_changes: route_changes(&self.to_graph(), &after.to_graph()),
+        }
+    }
+}
+
+impl Graph {
+    pub fn snapshot(&self, name: &str) -> Snapshot {
+        let mut nodes: Vec<Node> = self.nodes.values().cloned().collect();
+        nodes.sort_by(|a, b| a.id.cmp(&b.id));
+
+        let mut edges: Vec<Edge> = self.edges.values().cloned().collect();
+        edges.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+        let created_at = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|duration| duration.as_secs())
+            .unwrap_or(0);
+
+        Snapshot { name: name.to_string(), created_at, nodes, edges }
+    }
+}
+
+pub struct SnapshotStore {
+    snapshots: HashMap<String, Snapshot>,
+    directory: Option<PathBuf>,
+}
+
+impl SnapshotStore {
+    pub fn new() -> SnapshotStore {
+        SnapshotStore { snapshots: HashMap::new(), directory: None }
+    }
+
+    pub fn open(directory: PathBuf) -> SnapshotStore {
+        let mut snapshots = HashMap::new();
+
+        if let Ok(entries) = fs::read_dir(&directory) {
+            for entry in entries.flatten() {
+                let path = entry.path();
+                if path.extension().map_or(true, |extension| extension != "json") {
+                    continue;
+                }
+
+                match fs::read_to_string(&path).map_err(|err| err.to_string())
+                    .and_then(|contents| serde_json::from_str::<Snapshot>(&contents).map_err(|err| err.to_string())) {
+                    Ok(snapshot) => {
+                        snapshots.insert(snapshot.name.clone(), snapshot);
+                    }
+                    Err(err) => error!("Failed to load snapshot {:?}: {}", path, err),
+                }
+            }
+        }
+
+        SnapshotStore { snapshots, directory: Some(directory) }
+    }
+
+    pub fn save(&mut self, snapshot: Snapshot) -> Result<SnapshotSummary, String> {
+        validate_name(&snapshot.name)?;
+
+        if let Some(directory) = &self.directory {
+            fs::create_dir_all(directory).map_err(|err| err.to_string())?;
+            let contents = serde_json::to_string_pretty(&snapshot).map_err(|err| err.to_string())?;
+            fs::write(directory.join(format!("{}.json", snapshot.name)), contents).map_err(|err| err.to_string())?;
+        }
+
+        let summary = snapshot.summary();
+        self.snapshots.insert(snapshot.name.clone(), snapshot);
+        Ok(summary)
+    }
+
+    pub fn get(&self, name: &str) -> Result<&Snapshot, String> {
+        self.snapshots.get(name).ok_or(format!("Snapshot {} does not exist", name))
+    }
+
+    pub fn list(&self) -> Vec<SnapshotSummary> {
+        let mut summaries: Vec<SnapshotSummary> = self.snapshots.values().map(|snapshot| snapshot.summary()).collect();
+        summaries.sort_by(|a, b| a.created_at.cmp(&b.created_at).then(a.name.cmp(&b.name)));
+        summaries
+    }
+
+    pub fn delete(&mut self, name: &str) -> Result<(), String> {
+        if self.snapshots.remove(name).is_none() {
+            return Err(format!("Snapshot {} does not exist", name));
+        }
+
+        if let Some(directory) = &self.directory {
+            let path = directory.join(format!("{}.json", name));
+            if path.exists() {
+                fs::remove_file(path).map_err(|err| err.to_string())?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+fn validate_name(name: &str) -> Result<(), String> {
+    if name.is_empty() || !name.chars().all(|c| c.is_ascii_alphanumeric() || c == '-' || c == '_') {
+        return Err("Snapshot names may only contain letters, digits, '-' and '_'".to_string());
+    }
+
+    Ok(())
+}
+
+fn route_changes(before: &Graph, after: &Graph) -> Vec<RouteChange> {
+    let before_routes = before.available_view().floyd_warshall_map();
+    let after_routes = after.available_view().floyd_warshall_map();
+
+    let pairs: BTreeSet<&(NodeId, NodeId)> = before_routes.keys().chain(after_routes.keys()).collect();
+
+    pairs
+        .into_iter()
+        .filter_map(|pair| {
+            let before_route = before_routes.get(pair);
+            let after_route = after_routes.get(pair);
+            if before_route == after_route {
+                return None;
+            }
+
+            Some(RouteChange {
+                source: pair.0.clone(),
+                target: pair.1.clone(),
+                before: before_route.cloned(),
+                after: after_route.cloned(),
+            })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+
+    #[test]
+    fn outages_show_up_as_route_changes() {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph.add_edge("B".to_string(), "C".to_string(), 1).unwrap();
+        graph.add_edge("A".to_string(), "C".to_string(), 5).unwrap();
+        let before = graph.snapshot("before");
+
+        graph.set_node_availability("B", false).unwrap();
+        let diff = before.diff(&graph.snapshot("after"));
+
+        assert_eq!(diff.availability_changes.len(), 1);
+        let change = diff.route_changes.iter().find(|change| change.source == "A" && change.target == "C").unwrap();
+        assert_eq!(change.before, Some((vec!["A".to_string(), "B".to_string(), "C".to_string()], 2)));
+        assert_eq!(change.after, Some((vec!["A".to_string(), "C".to_string()], 5)));
+
+        let lost = diff.route_changes.iter().find(|change| change.source == "A" && change.target == "B").unwrap();
+        assert_eq!(lost.after, None);
+    }
+}
\ No newline at end of file
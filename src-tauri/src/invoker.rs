@@ -1,11 +1,34 @@
 use std::collections::{HashMap, HashSet};
+use std::fs::{self, File};
+use std::io::BufWriter;
 use serde::Serialize;
 use std::sync::{Arc, Mutex};
-use tauri::State;
+use log::error;
+use tauri::{AppHandle, Manager, State};
+use crate::api::{self, after_update, SerializableGraphState, SerializablePath};
+use crate::graph::algorithm::{self, AlgorithmInfo};
+use crate::graph::centrality::Centrality;
+use crate::graph::circuit::{Circuit, CircuitConstraints};
+use crate::graph::ecmp::{EqualCostPaths, DEFAULT_ECMP_LIMIT};
 use crate::graph::edge::Edge;
-use crate::graph::graph::Graph;
-use crate::graph::node::NodeId;
-use crate::utils::router::Router;
+use crate::graph::event::{GraphChange, GraphEvent, GRAPH_CHANGED_EVENT};
+use crate::graph::failure::{FailureImpact, FailureSet};
+use crate::graph::flow::MaxFlow;
+use crate::graph::history::{BatchResult, EditOperation, HistoryStatus};
+use crate::graph::node::{Behavior, NodeId};
+use crate::graph::route_table::RouteMemoryStats;
+use crate::graph::security::PathSecurity;
+use crate::graph::snapshot::{SnapshotDiff, SnapshotStore, SnapshotSummary};
+use crate::state::AppState;
+use crate::utils::emulation::{self, EmulationConfig, EmulationReport};
+use crate::utils::forwarding::{self, ForwardingConfig, ForwardingReport};
+use crate::utils::netns::{self, NetnsOptions};
+use crate::utils::packet::{EncryptedDelivery, OnionSimulator};
+use crate::utils::pcap;
+use crate::utils::route_worker::{RouteListener, RouteProgress, RouteWorker, ROUTES_PROGRESS_EVENT};
+use crate::utils::scenario::{ScenarioConfig, ScenarioEngine, ScenarioReport};
+use crate::utils::secret_sharing::{self, SecretSharingConfig, SecretSharingReport};
+use crate::utils::traffic::{simulate_flows, Demand, FlowReport};
 
 #[derive(Serialize, Clone)]
 pub struct GraphState {
@@ -13,112 +36,356 @@ pub struct GraphState {
     edges: HashMap<(NodeId, NodeId), Edge>,
 }
 
-#[derive(Serialize)]
-pub struct SerializableGraphState {
-    nodes: Vec<GraphNode>,
-    edges: Vec<GraphEdge>,
+#[tauri::command]
+pub fn get_graph(state: State<Arc<AppState>>, with_centrality: Option<bool>) -> SerializableGraphState {
+    api::get_graph(&state.read(), with_centrality.unwrap_or(false))
+}
+
+#[tauri::command]
+pub fn add_node(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, id: NodeId) -> Result<(), String> {
+    let changes = api::add_node(&state, id)?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn add_edge(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, source: NodeId, target: NodeId, cost: u32) -> Result<(), String> {
+    let changes = api::add_edge(&state, source, target, cost)?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
 }
 
-#[derive(Serialize)]
-pub struct GraphEdge {
-    source: String,
-    target: String,
-    cost: u32,
+#[tauri::command]
+pub fn remove_node(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, id: NodeId) -> Result<(), String> {
+    let changes = api::remove_node(&state, id)?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
 }
 
-#[derive(Serialize)]
-pub struct GraphNode {
-    id: String,
-    availability: bool,
+#[tauri::command]
+pub fn remove_edge(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, source: NodeId, target: NodeId) -> Result<(), String> {
+    let changes = api::remove_edge(&state, source, target)?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
 }
 
-#[derive(Serialize)]
-pub struct SerializablePath {
-    path: Vec<NodeId>,
-    cost: u32
+#[tauri::command]
+pub fn set_edge_capacity(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, source: NodeId, target: NodeId, capacity: Option<u32>) -> Result<(), String> {
+    let (_, changes) = state.edit(|topology, history| {
+        history.apply(topology.graph_mut(), &EditOperation::SetEdgeCapacity { source, target, capacity })
+    })?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
 }
 
 #[tauri::command]
-pub fn get_graph(state: State<Arc<Mutex<Graph>>>) -> SerializableGraphState {
-    let mut graph = state.lock().unwrap();
+pub fn set_edge_loss(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, source: NodeId, target: NodeId, loss: f64) -> Result<(), String> {
+    let (_, changes) = state.edit(|topology, history| {
+        history.apply(topology.graph_mut(), &EditOperation::SetEdgeLoss { source, target, loss })
+    })?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
+}
 
-    let (nodes_field, edges_field) = graph.get_fields();
+#[tauri::command]
+pub fn set_node_availability(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, id: NodeId, available: bool) -> Result<(), String> {
+    let changes = api::set_node_availability(&state, id, available)?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
+}
 
-    let nodes: Vec<GraphNode> = nodes_field
-        .into_iter()
-        .map(|(node_id, node)| GraphNode {
-            id: node_id.to_string(),
-            availability: node.available
-        })
-        .collect();
+#[tauri::command]
+pub fn set_node_compromise(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, id: NodeId, compromise: f64) -> Result<(), String> {
+    let (_, changes) = state.edit(|topology, history| {
+        history.apply(topology.graph_mut(), &EditOperation::SetNodeCompromise { id, compromise })
+    })?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
+}
 
-    let edges: Vec<GraphEdge> = edges_field
-        .into_iter()
-        .map(|((source, target), edge)| GraphEdge {
-            source: source.to_string(),
-            target: target.to_string(),
-            cost: edge.cost,
-        })
-        .collect();
+#[tauri::command]
+pub fn set_node_behavior(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, id: NodeId, behavior: Behavior) -> Result<(), String> {
+    let (_, changes) = state.edit(|topology, history| {
+        history.apply(topology.graph_mut(), &EditOperation::SetNodeBehavior { id, behavior })
+    })?;
+    after_update(&app, &state, &worker, changes);
+    Ok(())
+}
 
-    SerializableGraphState { nodes, edges }
+#[tauri::command]
+pub fn get_shortest_path(state: State<Arc<AppState>>, start: NodeId, target: NodeId) -> SerializablePath {
+    api::get_shortest_path(&state.read(), start, target)
 }
 
 #[tauri::command]
-pub fn add_node(state: State<Arc<Mutex<Graph>>>, id: NodeId) -> Result<(), String> {
-    let mut graph = state.lock().unwrap();
-    graph.add_node(&id)
+pub fn route_packet(state: State<Arc<AppState>>, start: NodeId, target: NodeId, flow: Option<u64>) -> SerializablePath {
+    api::route_packet(&state.read(), start, target, flow)
 }
 
 #[tauri::command]
-pub fn add_edge(router_state: State<Arc<Mutex<Router>>>, graph_state: State<Arc<Mutex<Graph>>>, source: NodeId, target: NodeId, cost: u32) -> Result<(), String> {
-    let mut graph = graph_state.lock().unwrap();
-    graph.add_edge(source, target, cost).expect("Failed to add edge");
+pub fn send_encrypted_packet(state: State<Arc<AppState>>, start: NodeId, target: NodeId, payload: String, flow: Option<u64>, tamper_at: Option<Vec<NodeId>>, seed: Option<u64>) -> Result<EncryptedDelivery, String> {
+    let topology = state.read();
+    let path = match flow {
+        Some(flow) => topology.router.route_packet_flow(&start, &target, flow, &topology.graph, &topology.router.neighbors(&topology.graph)),
+        None => topology.router.route_packet_v2(&start, &target, &topology.graph),
+    }?;
 
-    let mut router = router_state.lock().unwrap();
-    router.routes = graph.floyd_warshall_map();
-    Ok(())
+    let mut simulator = OnionSimulator::new(&topology.graph, seed);
+    let packet = simulator.packet(&start, &target, payload.into_bytes());
+    simulator.transmit(&packet, &path, &tamper_at.unwrap_or_default().into_iter().collect())
 }
 
 #[tauri::command]
-pub fn remove_node(state: State<Arc<Mutex<Graph>>>, id: NodeId) -> Result<(), String> {
-    let mut graph = state.lock().unwrap();
-    graph.remove_node(&id)
+pub fn send_secret_shared(state: State<Arc<AppState>>, start: NodeId, target: NodeId, payload: String, config: SecretSharingConfig) -> Result<SecretSharingReport, String> {
+    secret_sharing::send_secret_shared(&state.read().graph, &start, &target, payload.as_bytes(), &config)
 }
 
 #[tauri::command]
-pub fn remove_edge(router_state: State<Arc<Mutex<Router>>>, graph_state: State<Arc<Mutex<Graph>>>, source: NodeId, target: NodeId) -> Result<(), String> {
-    let mut graph = graph_state.lock().unwrap();
-    graph.remove_edge(source, target).expect("Failed to add edge");
+pub fn get_equal_cost_paths(state: State<Arc<AppState>>, start: NodeId, target: NodeId, limit: Option<usize>) -> Option<EqualCostPaths> {
+    let topology = state.read();
+    topology.router.get_equal_cost_paths(&start, &target, limit.unwrap_or(DEFAULT_ECMP_LIMIT), &topology.graph)
+}
+
+#[tauri::command]
+pub fn evaluate_path_security(state: State<Arc<AppState>>, path: Vec<NodeId>) -> Result<PathSecurity, String> {
+    state.read().graph.evaluate_path_security(&path)
+}
+
+#[tauri::command]
+pub fn get_most_secure_path(state: State<Arc<AppState>>, start: NodeId, target: NodeId) -> Option<PathSecurity> {
+    state.read().graph.most_secure_path(&start, &target)
+}
 
-    let mut router = router_state.lock().unwrap();
-    router.routes = graph.floyd_warshall_map();
+#[tauri::command]
+pub fn build_circuit(state: State<Arc<AppState>>, source: NodeId, target: NodeId, constraints: Option<CircuitConstraints>) -> Result<Circuit, String> {
+    state.read().graph.build_circuit(&source, &target, &constraints.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn list_routing_algorithms(state: State<Arc<AppState>>) -> Vec<AlgorithmInfo> {
+    let active = state.read().router.algorithm.name();
+    algorithm::algorithms()
+        .iter()
+        .map(|algorithm| AlgorithmInfo { name: algorithm.name(), capabilities: algorithm.capabilities(), active: algorithm.name() == active })
+        .collect()
+}
+
+#[tauri::command]
+pub fn set_routing_algorithm(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, name: String) -> Result<(), String> {
+    let algorithm = algorithm::algorithm(&name)?;
+    let (_, changes) = state.update(|topology| {
+        topology.record(GraphEvent::AlgorithmChanged { name: algorithm.name().to_string() });
+        topology.router.algorithm = algorithm;
+        topology.invalidate_routes();
+        Ok(())
+    })?;
+    after_update(&app, &state, &worker, changes);
     Ok(())
 }
 
 #[tauri::command]
-pub fn set_node_availability(state: State<Arc<Mutex<Graph>>>, id: NodeId, available: bool) -> Result<(), String> {
-    let mut graph = state.lock().unwrap();
-    graph.set_node_availability(&*id, available)
+pub fn get_route_memory_stats(state: State<Arc<AppState>>) -> RouteMemoryStats {
+    state.read().router.memory_usage()
 }
 
 #[tauri::command]
-pub fn get_shortest_path(state: State<Arc<Mutex<Router>>>, start: NodeId, target: NodeId) -> SerializablePath {
-    let router = state.lock().unwrap();
-    if let Some(shortest_path) = router.get_shortest_path(start, target) {
-        let (path, cost) = shortest_path;
-        return SerializablePath { path, cost }
+pub fn get_centrality(state: State<Arc<AppState>>) -> HashMap<NodeId, Centrality> {
+    state.read().graph.centrality()
+}
+
+#[tauri::command]
+pub fn simulate_failure(state: State<Arc<AppState>>, nodes: Vec<NodeId>, edges: Vec<(NodeId, NodeId)>) -> Result<FailureImpact, String> {
+    state.read().graph.failure_impact(&FailureSet { nodes, edges })
+}
+
+#[tauri::command]
+pub fn rank_node_failures(state: State<Arc<AppState>>, limit: Option<usize>) -> Vec<FailureImpact> {
+    let mut impacts = state.read().graph.rank_single_node_failures();
+    if let Some(limit) = limit {
+        impacts.truncate(limit);
     }
 
-    SerializablePath { path: vec![], cost: 0 }
+    impacts
+}
+
+#[tauri::command]
+pub fn run_scenario(state: State<Arc<AppState>>, config: ScenarioConfig) -> Result<ScenarioReport, String> {
+    let mut engine = ScenarioEngine::new(&state.read().graph, config)?;
+    engine.run()
 }
 
 #[tauri::command]
-pub fn route_packet(router_state: State<Arc<Mutex<Router>>>, graph_state: State<Arc<Mutex<Graph>>>, start: NodeId, target: NodeId) -> SerializablePath {
-    let router = router_state.lock().unwrap();
-    if let Ok(path) = router.route_packet_v2(&start, &target, graph_state) {
-        return SerializablePath { path, cost: 0 }
+pub fn simulate_traffic(state: State<Arc<AppState>>, demands: Vec<Demand>) -> FlowReport {
+    let topology = state.read();
+    simulate_flows(&topology.graph, &topology.router, &demands)
+}
+
+#[tauri::command]
+pub fn simulate_forwarding(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, config: ForwardingConfig, learn: Option<bool>) -> Result<ForwardingReport, String> {
+    let topology = state.read();
+    let report = forwarding::simulate_forwarding(&topology.graph, &topology.router, &config);
+
+    if learn.unwrap_or(false) {
+        let (_, changes) = state.update(|topology| {
+            Arc::make_mut(&mut topology.reputation).observe(&report);
+            topology.record(GraphEvent::ReputationUpdated);
+            topology.invalidate_routes();
+            Ok(())
+        })?;
+        after_update(&app, &state, &worker, changes);
     }
 
-    SerializablePath { path: vec![], cost: 0 }
+    Ok(report)
+}
+
+#[tauri::command]
+pub fn export_pcap(state: State<Arc<AppState>>, path: String, config: ForwardingConfig) -> Result<usize, String> {
+    let topology = state.read();
+    let report = forwarding::simulate_forwarding(&topology.graph, &topology.router, &config);
+
+    let file = File::create(&path).map_err(|err| format!("Could not create {}: {}", path, err))?;
+    pcap::write_pcap(&topology.graph, &report, BufWriter::new(file)).map_err(|err| format!("Could not write {}: {}", path, err))
+}
+
+#[tauri::command]
+pub fn export_netns_script(state: State<Arc<AppState>>, path: String, options: Option<NetnsOptions>) -> Result<(), String> {
+    let topology = state.read();
+    let script = netns::netns_script(&topology.graph, &topology.router, &options.unwrap_or_default())?;
+
+    fs::write(&path, script).map_err(|err| format!("Could not write {}: {}", path, err))?;
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        fs::set_permissions(&path, fs::Permissions::from_mode(0o755)).map_err(|err| format!("Could not make {} executable: {}", path, err))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn run_emulation(state: State<'_, Arc<AppState>>, config: EmulationConfig) -> Result<EmulationReport, String> {
+    let topology = state.read();
+    emulation::emulate(&topology.graph, &topology.router, &config).await
+}
+
+#[tauri::command]
+pub fn get_max_flow(state: State<Arc<AppState>>, source: NodeId, target: NodeId) -> Result<MaxFlow, String> {
+    state.read().graph.max_flow(&source, &target)
+}
+
+#[tauri::command]
+pub fn undo(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>) -> Result<HistoryStatus, String> {
+    let (status, changes) = state.edit(|topology, history| {
+        if history.undo(topology.graph_mut())? {
+            topology.invalidate_routes();
+        }
+        Ok(history.status())
+    })?;
+    after_update(&app, &state, &worker, changes);
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn redo(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>) -> Result<HistoryStatus, String> {
+    let (status, changes) = state.edit(|topology, history| {
+        if history.redo(topology.graph_mut())? {
+            topology.invalidate_routes();
+        }
+        Ok(history.status())
+    })?;
+    after_update(&app, &state, &worker, changes);
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn apply_operations(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>, operations: Vec<EditOperation>) -> Result<BatchResult, String> {
+    let (result, changes) = state.edit(|topology, history| {
+        let result = history.apply_batch(topology.graph_mut(), &operations);
+        if result.committed && !operations.is_empty() {
+            topology.invalidate_routes();
+        }
+        Ok(result)
+    })?;
+    after_update(&app, &state, &worker, changes);
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn begin_transaction(state: State<Arc<AppState>>) -> Result<HistoryStatus, String> {
+    state.history(|history| {
+        history.begin_transaction()?;
+        Ok(history.status())
+    })
+}
+
+#[tauri::command]
+pub fn commit_transaction(state: State<Arc<AppState>>) -> Result<HistoryStatus, String> {
+    state.history(|history| {
+        history.commit_transaction()?;
+        Ok(history.status())
+    })
+}
+
+#[tauri::command]
+pub fn rollback_transaction(app: AppHandle, state: State<Arc<AppState>>, worker: State<RouteWorker>) -> Result<HistoryStatus, String> {
+    let (status, changes) = state.edit(|topology, history| {
+        if history.rollback_transaction(topology.graph_mut())? {
+            topology.invalidate_routes();
+        }
+        Ok(history.status())
+    })?;
+    after_update(&app, &state, &worker, changes);
+    Ok(status)
+}
+
+#[tauri::command]
+pub fn save_snapshot(snapshot_state: State<Arc<Mutex<SnapshotStore>>>, state: State<Arc<AppState>>, name: String) -> Result<SnapshotSummary, String> {
+    let snapshot = state.read().graph.snapshot(&name);
+    let mut snapshots = snapshot_state.lock().unwrap();
+    snapshots.save(snapshot)
+}
+
+#[tauri::command]
+pub fn list_snapshots(state: State<Arc<Mutex<SnapshotStore>>>) -> Vec<SnapshotSummary> {
+    let snapshots = state.lock().unwrap();
+    snapshots.list()
+}
+
+#[tauri::command]
+pub fn delete_snapshot(state: State<Arc<Mutex<SnapshotStore>>>, name: String) -> Result<(), String> {
+    let mut snapshots = state.lock().unwrap();
+    snapshots.delete(&name)
+}
+
+#[tauri::command]
+pub fn diff_snapshots(snapshot_state: State<Arc<Mutex<SnapshotStore>>>, state: State<Arc<AppState>>, before: String, after: Option<String>) -> Result<SnapshotDiff, String> {
+    let current = state.read().graph.snapshot("current");
+    let snapshots = snapshot_state.lock().unwrap();
+
+    let before = snapshots.get(&before)?;
+    let after = match &after {
+        Some(name) => snapshots.get(name)?,
+        None => &current,
+    };
+
+    Ok(before.diff(after))
+}
+
+fn emit_changes(app: &AppHandle, changes: Vec<GraphChange>) {
+    for change in changes {
+        if let Err(err) = app.emit_all(GRAPH_CHANGED_EVENT, change) {
+            error!("Failed to emit graph change: {:?}", err);
+        }
+    }
+}
+
+impl RouteListener for AppHandle {
+    fn progress(&self, progress: RouteProgress) {
+        if let Err(err) = self.emit_all(ROUTES_PROGRESS_EVENT, progress) {
+            error!("Failed to emit route progress: {:?}", err);
+        }
+    }
+
+    fn changes(&self, changes: Vec<GraphChange>) {
+        emit_changes(self, changes);
+    }
 }
\ No newline at end of file
@@ -3,44 +3,61 @@
 #![allow(dead_code)]
 
 use std::sync::{Arc, Mutex};
+use std::time::Duration;
 use log::info;
 use crate::graph::graph::Graph;
+use crate::state::{AppState, Topology};
 
-use crate::invoker::{add_edge, add_node, get_graph, remove_node, remove_edge, get_shortest_path, set_node_availability, route_packet};
+use crate::invoker::{add_edge, add_node, get_graph, remove_node, remove_edge, get_shortest_path, set_node_availability, route_packet, get_centrality, simulate_failure, rank_node_failures, run_scenario, set_edge_capacity, simulate_traffic, get_max_flow, undo, redo, begin_transaction, commit_transaction, rollback_transaction, save_snapshot, list_snapshots, delete_snapshot, diff_snapshots, apply_operations, get_route_memory_stats, get_equal_cost_paths, set_node_compromise, evaluate_path_security, get_most_secure_path, build_circuit, send_encrypted_packet, send_secret_shared, set_node_behavior, simulate_forwarding, set_edge_loss, run_emulation, export_pcap, export_netns_script, list_routing_algorithms, set_routing_algorithm};
 
+use tauri::Manager;
 use tauri_plugin_log::{LogTarget};
-use crate::utils::router::Router;
+use crate::graph::snapshot::SnapshotStore;
+use crate::utils::route_worker::RouteWorker;
 
+mod api;
 mod graph;
 mod utils;
 mod invoker;
+mod server;
+mod state;
 
 fn main() {
-    let graph = Arc::new(Mutex::new(Graph::new()));
+    let mut graph = Graph::new();
 
-    graph.lock().unwrap().add_edge("A".to_string(), "B".to_string(), 100).unwrap();
-    graph.lock().unwrap().add_edge("A".to_string(), "C".to_string(), 56).unwrap();
-    graph.lock().unwrap().add_edge("B".to_string(), "E".to_string(), 14).unwrap();
-    graph.lock().unwrap().add_edge("C".to_string(), "D".to_string(), 77).unwrap();
-    graph.lock().unwrap().add_edge("E".to_string(), "F".to_string(), 56).unwrap();
-    graph.lock().unwrap().add_edge("E".to_string(), "G".to_string(), 75).unwrap();
-    graph.lock().unwrap().add_edge("G".to_string(), "C".to_string(), 86).unwrap();
-    graph.lock().unwrap().add_edge("H".to_string(), "C".to_string(), 81).unwrap();
-    graph.lock().unwrap().add_edge("H".to_string(), "F".to_string(), 14).unwrap();
-    graph.lock().unwrap().add_edge("F".to_string(), "C".to_string(), 76).unwrap();
-    graph.lock().unwrap().add_edge("F".to_string(), "A".to_string(), 66).unwrap();
-    graph.lock().unwrap().add_edge("F".to_string(), "B".to_string(), 71).unwrap();
-    graph.lock().unwrap().add_edge("F".to_string(), "D".to_string(), 76).unwrap();
-    graph.lock().unwrap().add_edge("E".to_string(), "J".to_string(), 92).unwrap();
-    graph.lock().unwrap().add_edge("E".to_string(), "K".to_string(), 81).unwrap();
-    graph.lock().unwrap().add_edge("A".to_string(), "K".to_string(), 12).unwrap();
-    graph.lock().unwrap().add_edge("K".to_string(), "I".to_string(), 76).unwrap();
-    graph.lock().unwrap().add_edge("I".to_string(), "D".to_string(), 15).unwrap();
-    graph.lock().unwrap().add_edge("D".to_string(), "L".to_string(), 55).unwrap();
-    graph.lock().unwrap().add_edge("L".to_string(), "C".to_string(), 64).unwrap();
+    graph.add_edge("A".to_string(), "B".to_string(), 100).unwrap();
+    graph.add_edge("A".to_string(), "C".to_string(), 56).unwrap();
+    graph.add_edge("B".to_string(), "E".to_string(), 14).unwrap();
+    graph.add_edge("C".to_string(), "D".to_string(), 77).unwrap();
+    graph.add_edge("E".to_string(), "F".to_string(), 56).unwrap();
+    graph.add_edge("E".to_string(), "G".to_string(), 75).unwrap();
+    graph.add_edge("G".to_string(), "C".to_string(), 86).unwrap();
+    graph.add_edge("H".to_string(), "C".to_string(), 81).unwrap();
+    graph.add_edge("H".to_string(), "F".to_string(), 14).unwrap();
+    graph.add_edge("F".to_string(), "C".to_string(), 76).unwrap();
+    graph.add_edge("F".to_string(), "A".to_string(), 66).unwrap();
+    graph.add_edge("F".to_string(), "B".to_string(), 71).unwrap();
+    graph.add_edge("F".to_string(), "D".to_string(), 76).unwrap();
+    graph.add_edge("E".to_string(), "J".to_string(), 92).unwrap();
+    graph.add_edge("E".to_string(), "K".to_string(), 81).unwrap();
+    graph.add_edge("A".to_string(), "K".to_string(), 12).unwrap();
+    graph.add_edge("K".to_string(), "I".to_string(), 76).unwrap();
+    graph.add_edge("I".to_string(), "D".to_string(), 15).unwrap();
+    graph.add_edge("D".to_string(), "L".to_string(), 55).unwrap();
+    graph.add_edge("L".to_string(), "C".to_string(), 64).unwrap();
 
-    let routes = graph.lock().unwrap().floyd_warshall_map().clone();
-    let router = Arc::new(Mutex::new(Router::from(routes)));
+    let state = Arc::new(AppState::new(Topology::new(graph)));
+
+    // `--serve [address]` exposes the same operations over JSON-RPC without opening a window.
+    let mut args = std::env::args().skip_while(|arg| arg != "--serve");
+    if args.next().is_some() {
+        let address = args.next().unwrap_or_else(|| server::DEFAULT_ADDRESS.to_string());
+        if let Err(err) = server::run(state, &address) {
+            eprintln!("{}", err);
+            std::process::exit(1);
+        }
+        return;
+    }
 
     tauri::Builder::default()
         .plugin(tauri_plugin_log::Builder::default().targets([
@@ -49,8 +66,17 @@ fn main() {
             LogTarget::Stdout,
             LogTarget::Webview,
         ]).build())
-        .manage(graph)
-        .manage(router)
+        .manage(state.clone())
+        .setup(move |app| {
+            app.manage(RouteWorker::spawn(state, Duration::from_millis(150), app.handle()));
+
+            let snapshots = match app.path_resolver().app_data_dir() {
+                Some(directory) => SnapshotStore::open(directory.join("snapshots")),
+                None => SnapshotStore::new(),
+            };
+            app.manage(Arc::new(Mutex::new(snapshots)));
+            Ok(())
+        })
         .invoke_handler(tauri::generate_handler![
             get_graph,
             add_node,
@@ -59,7 +85,40 @@ fn main() {
             remove_edge,
             get_shortest_path,
             set_node_availability,
-            route_packet
+            route_packet,
+            get_centrality,
+            simulate_failure,
+            rank_node_failures,
+            run_scenario,
+            set_edge_capacity,
+            simulate_traffic,
+            get_max_flow,
+            undo,
+            redo,
+            begin_transaction,
+            commit_transaction,
+            rollback_transaction,
+            save_snapshot,
+            list_snapshots,
+            delete_snapshot,
+            diff_snapshots,
+            apply_operations,
+            get_route_memory_stats,
+            get_equal_cost_paths,
+            set_node_compromise,
+            evaluate_path_security,
+            get_most_secure_path,
+            build_circuit,
+            send_encrypted_packet,
+            send_secret_shared,
+            set_node_behavior,
+            simulate_forwarding,
+            set_edge_loss,
+            run_emulation,
+            export_pcap,
+            export_netns_script,
+            list_routing_algorithms,
+            set_routing_algorithm
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
@@ -0,0 +1,404 @@
+use std::sync::Arc;
+use std::time::Duration;
+use log::{error, info};
+use serde::Deserialize;
+use serde_json::{json, Value};
+use tokio::io::{AsyncBufReadExt, AsyncReadExt, AsyncWriteExt, BufReader};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::time::timeout;
+use crate::api;
+use crate::graph::event::GraphChange;
+use crate::graph::node::NodeId;
+use crate::state::AppState;
+use crate::utils::route_worker::{RouteListener, RouteProgress, RouteWorker};
+
+pub const DEFAULT_ADDRESS: &str = "127.0.0.1:7878";
+const MAX_BODY: usize = 1024 * 1024;
+const MAX_LINE: usize = 8 * 1024;
+const REQUEST_TIMEOUT: Duration = Duration::from_secs(10);
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+// Errors returned by the operations themselves, carrying the same message the Tauri command gives.
+const OPERATION_FAILED: i64 = -32000;
+
+const METHODS: [&str; 8] = [
+    "get_graph",
+    "add_node",
+    "add_edge",
+    "remove_node",
+    "remove_edge",
+    "get_shortest_path",
+    "set_node_availability",
+    "route_packet",
+];
+
+// Parameters are named like the arguments of the matching Tauri command.
+#[derive(Deserialize, Debug)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+enum Call {
+    GetGraph { with_centrality: Option<bool> },
+    AddNode { id: NodeId },
+    AddEdge { source: NodeId, target: NodeId, cost: u32 },
+    RemoveNode { id: NodeId },
+    RemoveEdge { source: NodeId, target: NodeId },
+    GetShortestPath { start: NodeId, target: NodeId },
+    SetNodeAvailability { id: NodeId, available: bool },
+    RoutePacket { start: NodeId, target: NodeId, flow: Option<u64> },
+}
+
+#[derive(Deserialize)]
+struct Request {
+    jsonrpc: String,
+    #[serde(default)]
+    id: Value,
+    method: String,
+    #[serde(default)]
+    params: Value,
+}
+
+#[derive(Clone, Copy)]
+pub struct LogListener;
+
+impl RouteListener for LogListener {
+    fn progress(&self, progress: RouteProgress) {
+        info!("Routes: {:?}", progress);
+    }
+
+    fn changes(&self, changes: Vec<GraphChange>) {
+        for change in changes {
+            info!("Graph change: {:?}", change);
+        }
+    }
+}
+
+// Serves the graph operations as JSON-RPC 2.0 over HTTP POST, one request per connection. Edits
+// queue a background route recomputation exactly like the desktop app does.
+pub struct Server {
+    state: Arc<AppState>,
+    worker: RouteWorker,
+    // How long a client gets to send its whole request before the connection is answered and closed.
+    request_timeout: Duration,
+}
+
+impl Server {
+    pub fn new(state: Arc<AppState>, debounce: Duration) -> Server {
+        let worker = RouteWorker::spawn(state.clone(), debounce, LogListener);
+        Server { state, worker, request_timeout: REQUEST_TIMEOUT }
+    }
+
+    pub async fn serve(self: Arc<Self>, listener: TcpListener) {
+        loop {
+            let (stream, _) = match listener.accept().await {
+                Ok(connection) => connection,
+                Err(err) => {
+                    error!("Failed to accept connection: {}", err);
+                    continue;
+                }
+            };
+
+            let server = self.clone();
+            tokio::spawn(async move {
+                if let Err(err) = server.respond(stream).await {
+                    error!("Failed to answer request: {}", err);
+                }
+            });
+        }
+    }
+
+    pub fn handle(&self, body: &[u8]) -> Value {
+        let request: Value = match serde_json::from_slice(body) {
+            Ok(request) => request,
+            Err(err) => return failure(Value::Null, PARSE_ERROR, err.to_string()),
+        };
+        let request = match serde_json::from_value::<Request>(request) {
+            Ok(request) if request.jsonrpc == "2.0" => request,
+            Ok(request) => return failure(request.id, INVALID_REQUEST, "Only JSON-RPC 2.0 is supported".to_string()),
+            Err(err) => return failure(Value::Null, INVALID_REQUEST, err.to_string()),
+        };
+
+        if !METHODS.contains(&request.method.as_str()) {
+            return failure(request.id, METHOD_NOT_FOUND, format!("Unknown method {}", request.method));
+        }
+        let params = if request.params.is_null() { json!({}) } else { request.params };
+        let call: Call = match serde_json::from_value(json!({ "method": request.method, "params": params })) {
+            Ok(call) => call,
+            Err(err) => return failure(request.id, INVALID_PARAMS, err.to_string()),
+        };
+
+        match self.call(call) {
+            Ok(result) => json!({ "jsonrpc": "2.0", "id": request.id, "result": result }),
+            Err(err) => failure(request.id, OPERATION_FAILED, err),
+        }
+    }
+
+    fn call(&self, call: Call) -> Result<Value, String> {
+        let state = &self.state;
+        let result = match call {
+            Call::GetGraph { with_centrality } => json!(api::get_graph(&state.read(), with_centrality.unwrap_or(false))),
+            Call::GetShortestPath { start, target } => json!(api::get_shortest_path(&state.read(), start, target)),
+            Call::RoutePacket { start, target, flow } => json!(api::route_packet(&state.read(), start, target, flow)),
+            Call::AddNode { id } => self.edit(api::add_node(state, id)?),
+            Call::AddEdge { source, target, cost } => self.edit(api::add_edge(state, source, target, cost)?),
+            Call::RemoveNode { id } => self.edit(api::remove_node(state, id)?),
+            Call::RemoveEdge { source, target } => self.edit(api::remove_edge(state, source, target)?),
+            Call::SetNodeAvailability { id, available } => self.edit(api::set_node_availability(state, id, available)?),
+        };
+
+        Ok(result)
+    }
+
+    fn edit(&self, changes: Vec<GraphChange>) -> Value {
+        api::after_update(&LogListener, &self.state, &self.worker, changes);
+        Value::Null
+    }
+
+    async fn respond(&self, stream: TcpStream) -> std::io::Result<()> {
+        let mut reader = BufReader::new(stream);
+
+        let (status, body) = match timeout(self.request_timeout, self.read_request(&mut reader)).await {
+            Ok(answer) => answer?,
+            Err(_) => ("408 Request Timeout", failure(Value::Null, INVALID_REQUEST, "Request was not received in time".to_string())),
+        };
+
+        let body = body.to_string();
+        let response = format!(
+            "HTTP/1.1 {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+            status,
+            body.len(),
+            body
+        );
+        let mut stream = reader.into_inner();
+        stream.write_all(response.as_bytes()).await?;
+        stream.shutdown().await
+    }
+
+    async fn read_request(&self, reader: &mut BufReader<TcpStream>) -> std::io::Result<(&'static str, Value)> {
+        let too_long = || ("431 Request Header Fields Too Large", failure(Value::Null, INVALID_REQUEST, "Header line is too long".to_string()));
+
+        let request_line = match read_line(reader).await? {
+            Some(line) => line,
+            None => return Ok(too_long()),
+        };
+        let mut content_length = Some(0);
+        loop {
+            let line = match read_line(reader).await? {
+                Some(line) => line,
+                None => return Ok(too_long()),
+            };
+            if line.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    content_length = value.trim().parse().ok();
+                }
+            }
+        }
+
+        if !request_line.starts_with("POST ") {
+            return Ok(("405 Method Not Allowed", failure(Value::Null, INVALID_REQUEST, "Send JSON-RPC requests with POST".to_string())));
+        }
+        let content_length = match content_length {
+            Some(content_length) => content_length,
+            None => return Ok(("400 Bad Request", failure(Value::Null, PARSE_ERROR, "Content-Length is not a number".to_string()))),
+        };
+        if content_length > MAX_BODY {
+            return Ok(("413 Payload Too Large", failure(Value::Null, INVALID_REQUEST, "Request body is too large".to_string())));
+        }
+
+        let mut body = vec![0u8; content_length];
+        reader.read_exact(&mut body).await?;
+        Ok(("200 OK", self.handle(&body)))
+    }
+}
+
+// None when the line goes on past MAX_LINE bytes; an empty line means the client stopped sending.
+async fn read_line(reader: &mut BufReader<TcpStream>) -> std::io::Result<Option<String>> {
+    let mut line = String::new();
+    (&mut *reader).take(MAX_LINE as u64).read_line(&mut line).await?;
+    if line.len() >= MAX_LINE && !line.ends_with('\n') {
+        return Ok(None);
+    }
+
+    Ok(Some(line))
+}
+
+fn failure(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+// Runs the server on its own runtime until the process is stopped; used when the app is started
+// with `--serve [address]` instead of opening a window.
+pub fn run(state: Arc<AppState>, address: &str) -> Result<(), String> {
+    let runtime = tokio::runtime::Runtime::new().map_err(|err| err.to_string())?;
+    runtime.block_on(async {
+        let listener = TcpListener::bind(address).await.map_err(|err| format!("Could not listen on {}: {}", address, err))?;
+        info!("Serving JSON-RPC on http://{}", address);
+
+        Arc::new(Server::new(state, Duration::from_millis(150))).serve(listener).await;
+        Ok(())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Duration;
+    use serde_json::{json, Value};
+    use tokio::io::{AsyncReadExt, AsyncWriteExt};
+    use tokio::net::{TcpListener, TcpStream};
+    use crate::graph::graph::Graph;
+    use crate::state::{AppState, Topology};
+    use super::{Server, MAX_LINE, REQUEST_TIMEOUT};
+
+    async fn start() -> String {
+        start_with(REQUEST_TIMEOUT).await
+    }
+
+    async fn start_with(request_timeout: Duration) -> String {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph.add_edge("B".to_string(), "C".to_string(), 1).unwrap();
+        graph.add_edge("A".to_string(), "C".to_string(), 5).unwrap();
+
+        let state = Arc::new(AppState::new(Topology::new(graph)));
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap().to_string();
+        let mut server = Server::new(state, Duration::from_millis(10));
+        server.request_timeout = request_timeout;
+        tokio::spawn(Arc::new(server).serve(listener));
+
+        address
+    }
+
+    async fn post(address: &str, body: &str) -> (String, Value) {
+        let request = format!(
+            "POST /rpc HTTP/1.1\r\nHost: {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\r\n{}",
+            address,
+            body.len(),
+            body
+        );
+        send(address, &request).await
+    }
+
+    async fn send(address: &str, request: &str) -> (String, Value) {
+        let mut stream = TcpStream::connect(address).await.unwrap();
+        stream.write_all(request.as_bytes()).await.unwrap();
+
+        let mut response = String::new();
+        stream.read_to_string(&mut response).await.unwrap();
+        let (head, body) = response.split_once("\r\n\r\n").unwrap();
+        let status = head.lines().next().unwrap().to_string();
+
+        (status, serde_json::from_str(body).unwrap())
+    }
+
+    async fn rpc(address: &str, method: &str, params: Value) -> Value {
+        let body = json!({ "jsonrpc": "2.0", "id": 1, "method": method, "params": params }).to_string();
+        post(address, &body).await.1
+    }
+
+    async fn shortest_path(address: &str, start: &str, target: &str) -> Value {
+        for _ in 0..100 {
+            let response = rpc(address, "get_shortest_path", json!({ "start": start, "target": target })).await;
+            if response["result"]["stale"] == json!(false) {
+                return response["result"].clone();
+            }
+            tokio::time::sleep(Duration::from_millis(20)).await;
+        }
+        panic!("routes were never recomputed");
+    }
+
+    #[tokio::test]
+    async fn edits_are_visible_to_queries_and_reroute() {
+        let address = start().await;
+
+        let path = shortest_path(&address, "A", "C").await;
+        assert_eq!(path["path"], json!(["A", "B", "C"]));
+        assert_eq!(path["cost"], json!(2));
+
+        assert_eq!(rpc(&address, "remove_edge", json!({ "source": "A", "target": "B" })).await["result"], Value::Null);
+        let path = shortest_path(&address, "A", "C").await;
+        assert_eq!(path["path"], json!(["A", "C"]));
+
+        rpc(&address, "add_node", json!({ "id": "D" })).await;
+        rpc(&address, "add_edge", json!({ "source": "C", "target": "D", "cost": 2 })).await;
+        rpc(&address, "set_node_availability", json!({ "id": "B", "available": false })).await;
+
+        let graph = rpc(&address, "get_graph", Value::Null).await["result"].clone();
+        assert_eq!(graph["nodes"].as_array().unwrap().len(), 4);
+        let b = graph["nodes"].as_array().unwrap().iter().find(|node| node["id"] == "B").unwrap();
+        assert_eq!(b["availability"], json!(false));
+
+        assert_eq!(shortest_path(&address, "A", "D").await["cost"], json!(7));
+        let routed = rpc(&address, "route_packet", json!({ "start": "A", "target": "D" })).await["result"].clone();
+        assert_eq!(routed["path"], json!(["A", "C", "D"]));
+    }
+
+    #[tokio::test]
+    async fn errors_follow_json_rpc() {
+        let address = start().await;
+
+        let response = rpc(&address, "add_node", json!({ "id": "A" })).await;
+        assert_eq!(response["error"]["code"], json!(-32000));
+        assert_eq!(response["id"], json!(1));
+
+        let response = rpc(&address, "remove_node", json!({ "id": "Z" })).await;
+        assert_eq!(response["error"]["code"], json!(-32000));
+
+        let response = rpc(&address, "drop_tables", json!({})).await;
+        assert_eq!(response["error"]["code"], json!(-32601));
+
+        let response = rpc(&address, "add_edge", json!({ "source": "A" })).await;
+        assert_eq!(response["error"]["code"], json!(-32602));
+
+        let (status, response) = post(&address, "{not json").await;
+        assert_eq!(status, "HTTP/1.1 200 OK");
+        assert_eq!(response["error"]["code"], json!(-32700));
+    }
+
+    #[tokio::test]
+    async fn malformed_http_gets_json_rpc_errors() {
+        let address = start().await;
+
+        let (status, response) = send(&address, "POST /rpc HTTP/1.1\r\nContent-Length: ten\r\n\r\n{}").await;
+        assert_eq!(status, "HTTP/1.1 400 Bad Request");
+        assert_eq!(response["error"]["code"], json!(-32700));
+
+        let (status, response) = send(&address, "GET /rpc HTTP/1.1\r\n\r\n").await;
+        assert_eq!(status, "HTTP/1.1 405 Method Not Allowed");
+        assert_eq!(response["jsonrpc"], json!("2.0"));
+        assert_eq!(response["error"]["code"], json!(-32600));
+
+        let header = format!("POST /rpc HTTP/1.1\r\n{}", "a".repeat(MAX_LINE));
+        let (status, response) = send(&address, &header).await;
+        assert_eq!(status, "HTTP/1.1 431 Request Header Fields Too Large");
+        assert_eq!(response["error"]["code"], json!(-32600));
+    }
+
+    #[tokio::test]
+    async fn idle_clients_are_timed_out() {
+        let address = start_with(Duration::from_millis(50)).await;
+
+        let (status, response) = send(&address, "POST /rpc HTTP/1.1\r\nContent-Length: 10\r\n").await;
+        assert_eq!(status, "HTTP/1.1 408 Request Timeout");
+        assert_eq!(response["error"]["code"], json!(-32600));
+    }
+
+    #[tokio::test]
+    async fn removing_a_node_reroutes_around_it() {
+        let address = start().await;
+        assert_eq!(shortest_path(&address, "A", "C").await["path"], json!(["A", "B", "C"]));
+
+        rpc(&address, "remove_node", json!({ "id": "B" })).await;
+        let stale = rpc(&address, "get_shortest_path", json!({ "start": "A", "target": "C" })).await;
+        assert_eq!(stale["result"]["stale"], json!(true));
+
+        let path = shortest_path(&address, "A", "C").await;
+        assert_eq!(path["path"], json!(["A", "C"]));
+        assert_eq!(path["cost"], json!(5));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,220 @@
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, RwLock};
+use crate::graph::event::{GraphChange, GraphEvent};
+use crate::graph::graph::Graph;
+use crate::graph::history::{History, HistoryStatus};
+use crate::graph::route_table::RouteTable;
+use crate::utils::reputation::Reputation;
+use crate::utils::router::Router;
+
+#[derive(Clone)]
+pub struct Topology {
+    pub graph: Arc<Graph>,
+    pub router: Router,
+    pub reputation: Arc<Reputation>,
+    pub version: u64,
+    pub routes_stale: bool,
+    // Last change sent out; `events` are the ones recorded by the edit in progress.
+    pub sequence: u64,
+    events: Vec<GraphEvent>,
+}
+
+impl Topology {
+    pub fn new(graph: Graph) -> Topology {
+        let router = Router::from_table(graph.dijkstra_route_table());
+        Topology {
+            graph: Arc::new(graph),
+            router,
+            reputation: Arc::default(),
+            version: 0,
+            routes_stale: false,
+            sequence: 0,
+            events: Vec::new(),
+        }
+    }
+
+    // Snapshots share the graph until one of them edits it.
+    pub fn graph_mut(&mut self) -> &mut Graph {
+        Arc::make_mut(&mut self.graph)
+    }
+
+    // Routes minimize cost inflated by reputation, so relays that keep dropping traffic are avoided
+    // whenever a comparable alternative exists.
+    pub fn route_table_cancellable(&self, progress: impl Fn(usize, usize) -> bool + Sync) -> Option<RouteTable> {
+        self.router.algorithm.all_pairs(&self.graph, &|source, target, cost| self.reputation.weight(source, target, cost), &progress)
+    }
+
+    pub fn invalidate_routes(&mut self) {
+        self.routes_stale = true;
+    }
+
+    pub fn record(&mut self, event: GraphEvent) {
+        self.events.push(event);
+    }
+
+    fn take_changes(&mut self) -> Vec<GraphChange> {
+        let events = std::mem::take(&mut self.events);
+        events
+            .into_iter()
+            .map(|event| {
+                self.sequence += 1;
+                GraphChange { sequence: self.sequence, event }
+            })
+            .collect()
+    }
+}
+
+// Readers clone the current Arc and never wait on a writer; writers are serialized, work on a
+// private copy and publish it in one swap, so the graph and its routes always change together. The
+// copy shares the graph, route table and reputation with the snapshot it came from, so only the
+// parts an edit touches are ever duplicated. The undo journal only changes under the writer lock, so
+// it lives behind it instead of in every snapshot.
+pub struct AppState {
+    current: RwLock<Arc<Topology>>,
+    writer: Mutex<History>,
+    version: AtomicU64,
+}
+
+impl AppState {
+    pub fn new(topology: Topology) -> AppState {
+        AppState {
+            version: AtomicU64::new(topology.version),
+            current: RwLock::new(Arc::new(topology)),
+            writer: Mutex::new(History::default()),
+        }
+    }
+
+    pub fn read(&self) -> Arc<Topology> {
+        self.current.read().unwrap().clone()
+    }
+
+    pub fn version(&self) -> u64 {
+        self.version.load(Ordering::SeqCst)
+    }
+
+    pub fn history_status(&self) -> HistoryStatus {
+        self.writer.lock().unwrap().status()
+    }
+
+    // For changes to the journal alone. The topology and its version stay as they are, so a route
+    // computation running meanwhile can still publish.
+    pub fn history<T>(&self, f: impl FnOnce(&mut History) -> Result<T, String>) -> Result<T, String> {
+        f(&mut self.writer.lock().unwrap())
+    }
+
+    pub fn update<T>(&self, f: impl FnOnce(&mut Topology) -> Result<T, String>) -> Result<(T, Vec<GraphChange>), String> {
+        self.edit(|topology, _| f(topology))
+    }
+
+    // Like `update`, for edits that go through the undo journal.
+    pub fn edit<T>(&self, f: impl FnOnce(&mut Topology, &mut History) -> Result<T, String>) -> Result<(T, Vec<GraphChange>), String> {
+        let mut history = self.writer.lock().unwrap();
+
+        let mut next = Topology::clone(&self.read());
+        let value = f(&mut next, &mut history);
+        // A failed edit publishes nothing, so whatever it made the journal report is dropped too.
+        let journaled = history.take_events();
+        let value = value?;
+        next.events.splice(0..0, journaled);
+        let changes = next.take_changes();
+        next.version += 1;
+
+        self.version.store(next.version, Ordering::SeqCst);
+        *self.current.write().unwrap() = Arc::new(next);
+        Ok((value, changes))
+    }
+
+    // Routes computed in the background only land if no edit happened while they were running.
+    pub fn publish_routes(&self, version: u64, table: RouteTable) -> Option<Vec<GraphChange>> {
+        let _writer = self.writer.lock().unwrap();
+
+        let mut next = Topology::clone(&self.read());
+        if next.version != version {
+            return None;
+        }
+
+        next.router.table = Arc::new(table);
+        next.router.reputation = next.reputation.clone();
+        next.routes_stale = false;
+        next.record(GraphEvent::RoutesRecomputed { routes: next.router.route_count() });
+        let changes = next.take_changes();
+
+        *self.current.write().unwrap() = Arc::new(next);
+        Some(changes)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use crate::graph::event::{GraphChange, GraphEvent};
+    use crate::graph::graph::Graph;
+    use crate::graph::history::EditOperation;
+    use super::{AppState, Topology};
+
+    #[test]
+    fn edits_share_what_they_do_not_touch() {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        let state = AppState::new(Topology::new(graph));
+        let before = state.read();
+
+        let (_, changes) = state.update(|topology| {
+            topology.invalidate_routes();
+            Ok(())
+        }).unwrap();
+        let after = state.read();
+        assert!(changes.is_empty());
+        assert!(Arc::ptr_eq(&before.graph, &after.graph));
+        assert!(Arc::ptr_eq(&before.router.table, &after.router.table));
+
+        let (_, changes) = state.edit(|topology, history| {
+            history.apply(topology.graph_mut(), &EditOperation::AddNode { id: "C".to_string() })
+        }).unwrap();
+        let edited = state.read();
+        assert_eq!(changes.len(), 1);
+        assert!(!Arc::ptr_eq(&after.graph, &edited.graph));
+        assert!(Arc::ptr_eq(&after.router.table, &edited.router.table));
+        assert!(Arc::ptr_eq(&after.reputation, &edited.reputation));
+        assert!(!after.graph.nodes.contains_key("C"));
+        assert_eq!(changes[0].sequence, 1);
+        assert_eq!(edited.sequence, 1);
+    }
+
+    #[test]
+    fn transactions_do_not_cancel_a_running_route_computation() {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        let state = AppState::new(Topology::new(graph));
+        state.update(|topology| {
+            topology.invalidate_routes();
+            Ok(())
+        }).unwrap();
+
+        let running = state.read();
+        state.history(|history| history.begin_transaction()).unwrap();
+        assert_eq!(state.version(), running.version);
+
+        let table = running.route_table_cancellable(|_, _| state.version() == running.version).unwrap();
+        assert!(state.publish_routes(running.version, table).is_some());
+        assert!(!state.read().routes_stale);
+        assert!(state.history_status().in_transaction);
+    }
+
+    #[test]
+    fn failed_edits_report_nothing() {
+        let state = AppState::new(Topology::new(Graph::new()));
+
+        let result = state.edit(|topology, history| {
+            history.apply(topology.graph_mut(), &EditOperation::AddNode { id: "A".to_string() })?;
+            history.apply(topology.graph_mut(), &EditOperation::RemoveNode { id: "B".to_string() })
+        });
+        assert!(result.is_err());
+
+        let (_, changes) = state.update(|topology| {
+            topology.record(GraphEvent::NodeAdded { id: "C".to_string() });
+            Ok(())
+        }).unwrap();
+        assert_eq!(changes, vec![GraphChange { sequence: 1, event: GraphEvent::NodeAdded { id: "C".to_string() } }]);
+    }
+}
\ No newline at end of file
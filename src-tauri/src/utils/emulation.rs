@@ -0,0 +1,359 @@
+use std::collections::HashMap;
+use std::net::SocketAddr;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use log::warn;
+use serde::{Deserialize, Serialize};
+use tokio::net::UdpSocket;
+use tokio::sync::mpsc::{unbounded_channel, UnboundedReceiver, UnboundedSender};
+use tokio::task::JoinSet;
+use tokio::time::{sleep, sleep_until, timeout_at};
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+use crate::utils::router::Router;
+
+const MAX_DATAGRAM: usize = 64 * 1024;
+const DEFAULT_TIMEOUT_MS: u64 = 2000;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct InjectedPacket {
+    pub source: NodeId,
+    pub target: NodeId,
+    #[serde(default)]
+    pub payload: String,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct EmulationConfig {
+    pub packets: Vec<InjectedPacket>,
+    #[serde(default = "default_ms_per_cost")]
+    pub ms_per_cost: u64,
+    pub timeout_ms: Option<u64>,
+    pub seed: Option<u64>,
+}
+
+fn default_ms_per_cost() -> u64 {
+    1
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EmulatedDelivery {
+    pub id: u64,
+    pub source: NodeId,
+    pub target: NodeId,
+    pub path: Vec<NodeId>,
+    pub payload: String,
+    pub latency_ms: u64,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EmulatedDrop {
+    pub id: u64,
+    pub node: NodeId,
+    pub reason: String,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct EmulationReport {
+    pub nodes: usize,
+    pub injected: usize,
+    pub delivered: Vec<EmulatedDelivery>,
+    pub dropped: Vec<EmulatedDrop>,
+    pub missing: Vec<u64>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+struct Datagram {
+    id: u64,
+    source: NodeId,
+    target: NodeId,
+    ttl: u32,
+    trail: Vec<NodeId>,
+    payload: String,
+}
+
+// What nodes tell the collector; data packets travel between node sockets, notices only go to the
+// collector.
+#[derive(Serialize, Deserialize, Clone, Debug)]
+#[serde(tag = "notice", rename_all = "snake_case")]
+enum Notice {
+    Delivered { datagram: Datagram },
+    Dropped { id: u64, node: NodeId, reason: String },
+}
+
+// Packets on a link all wait the same delay, so they leave in the order they were queued.
+struct Link {
+    queue: UnboundedSender<(tokio::time::Instant, Vec<u8>)>,
+    delay: Duration,
+    loss: f64,
+}
+
+struct Forwarder {
+    id: NodeId,
+    socket: Arc<UdpSocket>,
+    collector: SocketAddr,
+    // The link towards the next hop for every destination.
+    table: HashMap<NodeId, Arc<Link>>,
+    rng: StdRng,
+}
+
+// Every available node gets its own loopback UDP socket and task. Forwarding tables come from the
+// Router's next hops, links delay packets in proportion to their cost and drop them with the edge's
+// loss probability, and a collector socket injects packets and records how each one ended.
+pub async fn emulate(graph: &Graph, router: &Router, config: &EmulationConfig) -> Result<EmulationReport, String> {
+    emulate_in(&mut JoinSet::new(), graph, router, config).await
+}
+
+// Forwarders and links all run in `tasks`, which is shut down whichever way the run ends.
+async fn emulate_in(tasks: &mut JoinSet<()>, graph: &Graph, router: &Router, config: &EmulationConfig) -> Result<EmulationReport, String> {
+    let report = run(tasks, graph, router, config).await;
+    tasks.shutdown().await;
+    report
+}
+
+async fn run(tasks: &mut JoinSet<()>, graph: &Graph, router: &Router, config: &EmulationConfig) -> Result<EmulationReport, String> {
+    let collector = UdpSocket::bind("127.0.0.1:0").await.map_err(|err| err.to_string())?;
+    let collector_address = collector.local_addr().map_err(|err| err.to_string())?;
+
+    let mut ids: Vec<&NodeId> = graph.nodes.values().filter(|node| node.available).map(|node| &node.id).collect();
+    ids.sort();
+
+    let mut sockets = HashMap::new();
+    for id in &ids {
+        let socket = UdpSocket::bind("127.0.0.1:0").await.map_err(|err| err.to_string())?;
+        sockets.insert((*id).clone(), Arc::new(socket));
+    }
+    let mut addresses = HashMap::new();
+    for (id, socket) in &sockets {
+        addresses.insert(id.clone(), socket.local_addr().map_err(|err| err.to_string())?);
+    }
+
+    let seed = config.seed.unwrap_or_else(rand::random);
+    for (index, id) in ids.iter().enumerate() {
+        let mut links: HashMap<&NodeId, Arc<Link>> = HashMap::new();
+        let mut table = HashMap::new();
+        for target in &ids {
+            let next_hop = match router.table.next_hop(id, target) {
+                Some(next_hop) => next_hop,
+                None => continue,
+            };
+            let (address, edge) = match (addresses.get(next_hop), graph.edges.get(&((*id).clone(), next_hop.clone()))) {
+                (Some(address), Some(edge)) => (*address, edge),
+                _ => continue,
+            };
+
+            let link = links.entry(next_hop).or_insert_with(|| {
+                let (queue, packets) = unbounded_channel();
+                tasks.spawn(carry(sockets[*id].clone(), address, packets));
+                Arc::new(Link { queue, delay: Duration::from_millis(edge.cost as u64 * config.ms_per_cost), loss: edge.loss })
+            });
+            table.insert((*target).clone(), link.clone());
+        }
+
+        let forwarder = Forwarder {
+            id: (*id).clone(),
+            socket: sockets[*id].clone(),
+            collector: collector_address,
+            table,
+            rng: StdRng::seed_from_u64(seed.wrapping_add(index as u64)),
+        };
+        tasks.spawn(forwarder.run());
+    }
+
+    let mut report = EmulationReport { nodes: ids.len(), ..EmulationReport::default() };
+    let mut injected_at: HashMap<u64, Instant> = HashMap::new();
+
+    for (index, packet) in config.packets.iter().enumerate() {
+        let id = index as u64 + 1;
+        report.injected += 1;
+
+        let address = match addresses.get(&packet.source) {
+            Some(address) => address,
+            None => {
+                report.dropped.push(EmulatedDrop { id, node: packet.source.clone(), reason: "Source is not running".to_string() });
+                continue;
+            }
+        };
+
+        let datagram = Datagram {
+            id,
+            source: packet.source.clone(),
+            target: packet.target.clone(),
+            ttl: ids.len() as u32,
+            trail: Vec::new(),
+            payload: packet.payload.clone(),
+        };
+        let bytes = serde_json::to_vec(&datagram).map_err(|err| err.to_string())?;
+        collector.send_to(&bytes, address).await.map_err(|err| err.to_string())?;
+        injected_at.insert(id, Instant::now());
+    }
+
+    let deadline = tokio::time::Instant::now() + Duration::from_millis(config.timeout_ms.unwrap_or(DEFAULT_TIMEOUT_MS));
+    let mut buffer = vec![0u8; MAX_DATAGRAM];
+    while !injected_at.is_empty() {
+        let length = match timeout_at(deadline, collector.recv(&mut buffer)).await {
+            Ok(Ok(length)) => length,
+            Ok(Err(err)) => {
+                warn!("Emulation collector stopped receiving: {}", err);
+                break;
+            }
+            Err(_) => break,
+        };
+
+        match serde_json::from_slice::<Notice>(&buffer[..length]) {
+            Ok(Notice::Delivered { datagram }) => {
+                if let Some(sent) = injected_at.remove(&datagram.id) {
+                    report.delivered.push(EmulatedDelivery {
+                        id: datagram.id,
+                        source: datagram.source,
+                        target: datagram.target,
+                        path: datagram.trail,
+                        payload: datagram.payload,
+                        latency_ms: sent.elapsed().as_millis() as u64,
+                    });
+                }
+            }
+            Ok(Notice::Dropped { id, node, reason }) => {
+                if injected_at.remove(&id).is_some() {
+                    report.dropped.push(EmulatedDrop { id, node, reason });
+                }
+            }
+            Err(_) => continue,
+        }
+    }
+
+    report.missing = injected_at.into_keys().collect();
+    report.missing.sort();
+    report.delivered.sort_by_key(|delivery| delivery.id);
+    report.dropped.sort_by_key(|drop| drop.id);
+    Ok(report)
+}
+
+impl Forwarder {
+    async fn run(mut self) {
+        let mut buffer = vec![0u8; MAX_DATAGRAM];
+        loop {
+            let length = match self.socket.recv(&mut buffer).await {
+                Ok(length) => length,
+                // Loopback reports a closed peer as an error on the next receive; wait it out.
+                Err(_) => {
+                    sleep(Duration::from_millis(10)).await;
+                    continue;
+                }
+            };
+            let mut datagram: Datagram = match serde_json::from_slice(&buffer[..length]) {
+                Ok(datagram) => datagram,
+                Err(_) => continue,
+            };
+            datagram.trail.push(self.id.clone());
+
+            if datagram.target == self.id {
+                self.notify(Notice::Delivered { datagram }).await;
+                continue;
+            }
+            if datagram.ttl == 0 {
+                self.reject(&datagram, "Hop limit exceeded").await;
+                continue;
+            }
+
+            let link = match self.table.get(&datagram.target) {
+                Some(link) => link.clone(),
+                None => {
+                    self.reject(&datagram, "No route").await;
+                    continue;
+                }
+            };
+            if self.rng.gen_bool(link.loss) {
+                self.reject(&datagram, "Lost on link").await;
+                continue;
+            }
+
+            datagram.ttl -= 1;
+            let bytes = match serde_json::to_vec(&datagram) {
+                Ok(bytes) => bytes,
+                Err(_) => continue,
+            };
+            let _ = link.queue.send((tokio::time::Instant::now() + link.delay, bytes));
+        }
+    }
+
+    async fn reject(&self, datagram: &Datagram, reason: &str) {
+        self.notify(Notice::Dropped { id: datagram.id, node: self.id.clone(), reason: reason.to_string() }).await;
+    }
+
+    async fn notify(&self, notice: Notice) {
+        if let Ok(bytes) = serde_json::to_vec(&notice) {
+            let _ = self.socket.send_to(&bytes, self.collector).await;
+        }
+    }
+}
+
+async fn carry(socket: Arc<UdpSocket>, address: SocketAddr, mut packets: UnboundedReceiver<(tokio::time::Instant, Vec<u8>)>) {
+    while let Some((due, bytes)) = packets.recv().await {
+        sleep_until(due).await;
+        let _ = socket.send_to(&bytes, address).await;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use tokio::task::JoinSet;
+    use crate::graph::graph::Graph;
+    use crate::utils::router::Router;
+    use super::{emulate, emulate_in, EmulationConfig, InjectedPacket};
+
+    fn line() -> (Graph, Router) {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph.add_edge("B".to_string(), "C".to_string(), 1).unwrap();
+        graph.add_edge("A".to_string(), "D".to_string(), 5).unwrap();
+        graph.add_edge("D".to_string(), "C".to_string(), 5).unwrap();
+        let router = Router::from_table(graph.dijkstra_route_table());
+
+        (graph, router)
+    }
+
+    fn config(count: usize) -> EmulationConfig {
+        let packets = (0..count)
+            .map(|i| InjectedPacket { source: "A".to_string(), target: "C".to_string(), payload: format!("packet {}", i) })
+            .collect();
+        EmulationConfig { packets, ms_per_cost: 1, timeout_ms: Some(2000), seed: Some(1) }
+    }
+
+    #[tokio::test]
+    async fn packets_are_forwarded_hop_by_hop_over_sockets() {
+        let (graph, router) = line();
+        let report = emulate(&graph, &router, &config(5)).await.unwrap();
+
+        assert_eq!(report.nodes, 4);
+        assert_eq!(report.delivered.len(), 5);
+        for delivery in &report.delivered {
+            assert_eq!(delivery.path, vec!["A", "B", "C"]);
+            assert_eq!(delivery.payload, format!("packet {}", delivery.id - 1));
+        }
+    }
+
+    #[tokio::test]
+    async fn lossy_links_drop_packets() {
+        let (mut graph, router) = line();
+        graph.set_edge_loss("B", "C", 1.0).unwrap();
+        let report = emulate(&graph, &router, &config(3)).await.unwrap();
+
+        assert!(report.delivered.is_empty());
+        assert_eq!(report.dropped.len(), 3);
+        assert!(report.dropped.iter().all(|drop| drop.node == "B"));
+    }
+
+    #[tokio::test]
+    async fn failed_runs_stop_their_forwarders() {
+        let (graph, router) = line();
+        let mut config = config(1);
+        config.packets[0].payload = "x".repeat(super::MAX_DATAGRAM);
+
+        let mut tasks = JoinSet::new();
+        assert!(emulate_in(&mut tasks, &graph, &router, &config).await.is_err());
+        assert!(tasks.is_empty());
+    }
+}
\ No newline at end of file
@@ -0,0 +1,267 @@
+use std::collections::{BTreeMap, HashSet};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::graph::graph::Graph;
+use crate::graph::node::{Behavior, NodeId};
+use crate::utils::router::Router;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct TrafficSpec {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub packets: u32,
+}
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct ForwardingConfig {
+    pub traffic: Vec<TrafficSpec>,
+    #[serde(default)]
+    pub retries: u32,
+    pub seed: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "fate", rename_all = "snake_case")]
+pub enum HopFate {
+    Delivered { latency: u32, modified_by: Vec<NodeId>, delayed_by: Vec<NodeId> },
+    Dropped { node: NodeId },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct Attempt {
+    pub path: Vec<NodeId>,
+    #[serde(flatten)]
+    pub fate: HopFate,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct PacketTrace {
+    pub id: u64,
+    pub source: NodeId,
+    pub target: NodeId,
+    pub attempts: Vec<Attempt>,
+    pub delivered: bool,
+    pub intact: bool,
+    pub affected_by: Vec<NodeId>,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct NodeImpact {
+    pub dropped: usize,
+    pub modified: usize,
+    pub delayed: usize,
+}
+
+#[derive(Serialize, Clone, Debug, Default)]
+pub struct ForwardingReport {
+    pub sent: usize,
+    pub delivered: usize,
+    pub intact: usize,
+    pub unroutable: usize,
+    pub retried: usize,
+    pub affected: Vec<u64>,
+    pub by_node: BTreeMap<NodeId, NodeImpact>,
+    pub packets: Vec<PacketTrace>,
+}
+
+// Packets start on the path the Router picks for their flow. A dropped packet is retried on a path
+// that avoids every relay of the attempts that failed before it, since the sender cannot tell which
+// one of them swallowed it.
+pub fn simulate_forwarding(graph: &Graph, router: &Router, config: &ForwardingConfig) -> ForwardingReport {
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+    let mut report = ForwardingReport::default();
+    let neighbors = router.neighbors(graph);
+    let mut id = 0;
+
+    for spec in &config.traffic {
+        for flow in 0..spec.packets as u64 {
+            id += 1;
+            report.sent += 1;
+
+            let mut path = match router.route_packet_flow(&spec.source, &spec.target, flow, graph, &neighbors) {
+                Ok(path) => path,
+                Err(_) => {
+                    report.unroutable += 1;
+                    continue;
+                }
+            };
+
+            let mut attempts = Vec::new();
+            let mut suspects = HashSet::new();
+            loop {
+                let fate = forward(graph, &path, &mut rng);
+                let dropped = matches!(fate, HopFate::Dropped { .. });
+                if path.len() > 2 {
+                    suspects.extend(path[1..path.len() - 1].iter().cloned());
+                }
+                attempts.push(Attempt { path, fate });
+
+                if !dropped || attempts.len() > config.retries as usize {
+                    break;
+                }
+                match graph.dijkstra_re_path(&spec.source, &spec.target, &suspects) {
+                    Some(alternative) => path = alternative,
+                    None => break,
+                }
+            }
+
+            let trace = trace(id, spec, attempts);
+            record(&mut report, &trace);
+            report.packets.push(trace);
+        }
+    }
+
+    report
+}
+
+fn forward(graph: &Graph, path: &[NodeId], rng: &mut StdRng) -> HopFate {
+    let mut latency = 0;
+    let mut modified_by = Vec::new();
+    let mut delayed_by = Vec::new();
+
+    for (index, hop) in path.windows(2).enumerate() {
+        if index > 0 {
+            let node = &hop[0];
+            match graph.nodes.get(node).map(|node| &node.behavior) {
+                Some(Behavior::Blackhole) => return HopFate::Dropped { node: node.clone() },
+                Some(Behavior::Grayhole { drop_rate }) if rng.gen_bool(*drop_rate) => {
+                    return HopFate::Dropped { node: node.clone() };
+                }
+                Some(Behavior::Modifier) => modified_by.push(node.clone()),
+                Some(Behavior::Delayer { delay }) => {
+                    latency += delay;
+                    delayed_by.push(node.clone());
+                }
+                _ => {}
+            }
+        }
+
+        latency += graph.edges.get(&(hop[0].clone(), hop[1].clone())).map_or(0, |edge| edge.cost);
+    }
+
+    HopFate::Delivered { latency, modified_by, delayed_by }
+}
+
+fn trace(id: u64, spec: &TrafficSpec, attempts: Vec<Attempt>) -> PacketTrace {
+    let mut affected_by: Vec<NodeId> = Vec::new();
+    for attempt in &attempts {
+        let nodes = match &attempt.fate {
+            HopFate::Dropped { node } => vec![node],
+            HopFate::Delivered { modified_by, delayed_by, .. } => modified_by.iter().chain(delayed_by).collect(),
+        };
+        for node in nodes {
+            if !affected_by.contains(node) {
+                affected_by.push(node.clone());
+            }
+        }
+    }
+
+    let (delivered, intact) = match attempts.last().map(|attempt| &attempt.fate) {
+        Some(HopFate::Delivered { modified_by, .. }) => (true, modified_by.is_empty()),
+        _ => (false, false),
+    };
+
+    PacketTrace {
+        id,
+        source: spec.source.clone(),
+        target: spec.target.clone(),
+        attempts,
+        delivered,
+        intact,
+        affected_by,
+    }
+}
+
+fn record(report: &mut ForwardingReport, trace: &PacketTrace) {
+    report.delivered += trace.delivered as usize;
+    report.intact += trace.intact as usize;
+    report.retried += (trace.attempts.len() > 1) as usize;
+    if !trace.affected_by.is_empty() {
+        report.affected.push(trace.id);
+    }
+
+    for attempt in &trace.attempts {
+        match &attempt.fate {
+            HopFate::Dropped { node } => report.by_node.entry(node.clone()).or_default().dropped += 1,
+            HopFate::Delivered { modified_by, delayed_by, .. } => {
+                for node in modified_by {
+                    report.by_node.entry(node.clone()).or_default().modified += 1;
+                }
+                for node in delayed_by {
+                    report.by_node.entry(node.clone()).or_default().delayed += 1;
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+    use crate::graph::node::Behavior;
+    use crate::utils::router::Router;
+    use super::{simulate_forwarding, ForwardingConfig, ForwardingReport, HopFate, TrafficSpec};
+
+    // A-B-C-D is the shortest route; A-E-D is the detour around a misbehaving B.
+    fn run(behavior: Behavior, retries: u32) -> ForwardingReport {
+        let mut graph = Graph::new();
+        for (source, target, cost) in [("A", "B", 1), ("B", "C", 1), ("C", "D", 1), ("A", "E", 2), ("E", "D", 3)] {
+            graph.add_edge(source.to_string(), target.to_string(), cost).unwrap();
+        }
+        graph.set_node_behavior("B", behavior).unwrap();
+
+        let router = Router::from_table(graph.dijkstra_route_table());
+        let config = ForwardingConfig {
+            traffic: vec![TrafficSpec { source: "A".to_string(), target: "D".to_string(), packets: 4 }],
+            retries,
+            seed: Some(7),
+        };
+        simulate_forwarding(&graph, &router, &config)
+    }
+
+    #[test]
+    fn blackholes_drop_everything_until_retried_around() {
+        let report = run(Behavior::Blackhole, 0);
+        assert_eq!((report.sent, report.delivered), (4, 0));
+        assert_eq!(report.by_node["B"].dropped, 4);
+        assert_eq!(report.packets[0].attempts[0].fate, HopFate::Dropped { node: "B".to_string() });
+
+        let report = run(Behavior::Blackhole, 1);
+        assert_eq!((report.delivered, report.intact, report.retried), (4, 4, 4));
+        assert_eq!(report.packets[0].attempts[1].path, vec!["A", "E", "D"]);
+        assert_eq!(report.affected.len(), 4);
+    }
+
+    #[test]
+    fn grayholes_drop_at_their_rate() {
+        assert_eq!(run(Behavior::Grayhole { drop_rate: 0.0 }, 0).delivered, 4);
+        assert_eq!(run(Behavior::Grayhole { drop_rate: 1.0 }, 0).delivered, 0);
+
+        let report = run(Behavior::Grayhole { drop_rate: 0.5 }, 0);
+        assert_eq!(report.delivered + report.by_node.get("B").map_or(0, |impact| impact.dropped), 4);
+    }
+
+    #[test]
+    fn modifiers_deliver_tampered_packets() {
+        let report = run(Behavior::Modifier, 1);
+        assert_eq!((report.delivered, report.intact, report.retried), (4, 0, 0));
+        assert_eq!(report.by_node["B"].modified, 4);
+        assert_eq!(report.packets[0].affected_by, vec!["B"]);
+    }
+
+    #[test]
+    fn delayers_add_to_the_latency() {
+        let report = run(Behavior::Delayer { delay: 5 }, 0);
+        assert_eq!((report.delivered, report.intact), (4, 4));
+        assert_eq!(report.by_node["B"].delayed, 4);
+        assert_eq!(report.packets[0].attempts[0].fate, HopFate::Delivered {
+            latency: 8,
+            modified_by: vec![],
+            delayed_by: vec!["B".to_string()],
+        });
+    }
+}
\ No newline at end of file
@@ -1,3 +1,13 @@
 pub mod utils;
+pub mod emulation;
+pub mod forwarding;
+pub mod netns;
+pub mod packet;
 pub mod packet_sender;
-pub mod router;
\ No newline at end of file
+pub mod pcap;
+pub mod reputation;
+pub mod route_worker;
+pub mod router;
+pub mod scenario;
+pub mod secret_sharing;
+pub mod traffic;
\ No newline at end of file
@@ -0,0 +1,224 @@
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::fmt::Write;
+use std::net::Ipv4Addr;
+use serde::Deserialize;
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+use crate::utils::router::Router;
+
+// Each node is reachable at a /32 from this block, each link gets a /30 from LINK_BLOCK.
+const NODE_BLOCK: u32 = 0xac10_0000;
+const LINK_BLOCK: u32 = 0x0a00_0000;
+
+#[derive(Deserialize, Clone, Debug, Default)]
+pub struct NetnsOptions {
+    pub prefix: Option<String>,
+    pub ms_per_cost: Option<u32>,
+}
+
+struct Link<'a> {
+    ends: [(&'a NodeId, String, Ipv4Addr); 2],
+}
+
+// Builds a POSIX shell script that recreates the topology with network namespaces: one namespace
+// per available node, a veth pair per link with netem delay proportional to the edge cost (and the
+// edge's loss), and static routes that follow the Router's next hops. Running the script with
+// `down` removes everything again.
+pub fn netns_script(graph: &Graph, router: &Router, options: &NetnsOptions) -> Result<String, String> {
+    let prefix = options.prefix.clone().unwrap_or_else(|| "msp".to_string());
+    if prefix.is_empty() || prefix.len() > 32 || !prefix.chars().all(is_name_char) {
+        return Err("Namespace prefix may only contain letters, digits, '-' and '_'".to_string());
+    }
+    let ms_per_cost = options.ms_per_cost.unwrap_or(1);
+
+    let mut ids: Vec<&NodeId> = graph.nodes.values().filter(|node| node.available).map(|node| &node.id).collect();
+    ids.sort();
+    if ids.len() >= 1 << 20 {
+        return Err("Too many nodes for the address plan".to_string());
+    }
+
+    let mut used = HashSet::new();
+    let mut namespaces: HashMap<&NodeId, String> = HashMap::new();
+    let mut addresses: HashMap<&NodeId, Ipv4Addr> = HashMap::new();
+    for (index, id) in ids.iter().enumerate() {
+        let sanitized: String = id.chars().map(|c| if is_name_char(c) { c } else { '_' }).collect();
+        let mut name = format!("{}-{}", prefix, sanitized);
+        let mut suffix = index;
+        while !used.insert(name.clone()) {
+            name = format!("{}-{}-{}", prefix, sanitized, suffix);
+            suffix += 1;
+        }
+
+        namespaces.insert(id, name);
+        addresses.insert(id, Ipv4Addr::from(NODE_BLOCK + index as u32 + 1));
+    }
+
+    let mut pairs: BTreeSet<(&NodeId, &NodeId)> = BTreeSet::new();
+    for (source, target) in graph.edges.keys() {
+        if source != target && namespaces.contains_key(source) && namespaces.contains_key(target) {
+            pairs.insert(if source < target { (source, target) } else { (target, source) });
+        }
+    }
+    if pairs.len() >= 1 << 22 {
+        return Err("Too many links for the address plan".to_string());
+    }
+
+    let links: Vec<Link> = pairs
+        .iter()
+        .enumerate()
+        .map(|(index, (a, b))| {
+            let subnet = LINK_BLOCK + index as u32 * 4;
+            Link {
+                ends: [
+                    (*a, format!("l{}a", index), Ipv4Addr::from(subnet + 1)),
+                    (*b, format!("l{}b", index), Ipv4Addr::from(subnet + 2)),
+                ],
+            }
+        })
+        .collect();
+
+    // For every node, the interface and neighbor address towards each adjacent node.
+    let mut interfaces: HashMap<(&NodeId, &NodeId), (&str, Ipv4Addr)> = HashMap::new();
+    for link in &links {
+        let [(a, a_interface, a_address), (b, b_interface, b_address)] = &link.ends;
+        interfaces.insert((*a, *b), (a_interface.as_str(), *b_address));
+        interfaces.insert((*b, *a), (b_interface.as_str(), *a_address));
+    }
+
+    let mut script = String::new();
+    let out = &mut script;
+    let _ = writeln!(out, "#!/bin/sh");
+    let _ = writeln!(out, "# Generated topology: {} namespaces, {} links. Run as root; pass `down` to tear it down.", ids.len(), links.len());
+    let _ = writeln!(out, "set -e");
+    let _ = writeln!(out);
+    let _ = writeln!(out, "if [ \"$1\" = \"down\" ]; then");
+    for id in &ids {
+        let _ = writeln!(out, "    ip netns del {} 2>/dev/null || true", namespaces[id]);
+    }
+    let _ = writeln!(out, "    exit 0");
+    let _ = writeln!(out, "fi");
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "# Namespaces");
+    for id in &ids {
+        let namespace = &namespaces[id];
+        let _ = writeln!(out, "ip netns add {}  # {}", namespace, comment(id));
+        let _ = writeln!(out, "ip -n {} link set lo up", namespace);
+        let _ = writeln!(out, "ip -n {} addr add {}/32 dev lo", namespace, addresses[id]);
+        let _ = writeln!(out, "ip netns exec {} sysctl -qw net.ipv4.ip_forward=1", namespace);
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "# Links");
+    for link in &links {
+        let [(a, a_interface, _), (b, b_interface, _)] = &link.ends;
+        let _ = writeln!(out, "ip link add {} netns {} type veth peer name {} netns {}", a_interface, namespaces[a], b_interface, namespaces[b]);
+
+        for (index, (node, interface, address)) in link.ends.iter().enumerate() {
+            let peer = link.ends[1 - index].0;
+            let namespace = &namespaces[node];
+            let edge = graph.edges.get(&((*node).clone(), peer.clone())).or_else(|| graph.edges.get(&(peer.clone(), (*node).clone())));
+            let (cost, loss) = edge.map_or((0, 0.0), |edge| (edge.cost, edge.loss));
+
+            let _ = writeln!(out, "ip -n {} addr add {}/30 dev {}", namespace, address, interface);
+            let _ = writeln!(out, "ip -n {} link set {} up", namespace, interface);
+
+            let mut netem = format!("delay {}ms", cost as u64 * ms_per_cost as u64);
+            if loss > 0.0 {
+                let _ = write!(netem, " loss {}%", loss * 100.0);
+            }
+            let _ = writeln!(out, "tc -n {} qdisc add dev {} root netem {}", namespace, interface, netem);
+        }
+    }
+
+    let _ = writeln!(out);
+    let _ = writeln!(out, "# Routes");
+    for source in &ids {
+        for target in &ids {
+            if source == target {
+                continue;
+            }
+
+            let next_hop = match router.table.next_hop(source, target) {
+                Some(next_hop) => next_hop,
+                None => continue,
+            };
+            match interfaces.get(&(*source, next_hop)) {
+                Some((interface, via)) => {
+                    let _ = writeln!(
+                        out,
+                        "ip -n {} route add {}/32 via {} dev {} src {}  # {} -> {}",
+                        namespaces[source],
+                        addresses[target],
+                        via,
+                        interface,
+                        addresses[source],
+                        comment(source),
+                        comment(target)
+                    );
+                }
+                None => {
+                    let _ = writeln!(out, "# no link from {} to next hop {} for {}", comment(source), comment(next_hop), comment(target));
+                }
+            }
+        }
+    }
+
+    Ok(script)
+}
+
+fn is_name_char(c: char) -> bool {
+    c.is_ascii_alphanumeric() || c == '-' || c == '_'
+}
+
+// Node ids end up in `#` comments; a line break would let the rest of the id run as a command.
+fn comment(text: &str) -> String {
+    text.chars().map(|c| if c.is_control() { '?' } else { c }).collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+    use crate::utils::router::Router;
+    use super::{netns_script, NetnsOptions};
+
+    fn line(ids: [&str; 3]) -> (Graph, Router) {
+        let mut graph = Graph::new();
+        graph.add_edge(ids[0].to_string(), ids[1].to_string(), 2).unwrap();
+        graph.add_edge(ids[1].to_string(), ids[2].to_string(), 3).unwrap();
+        graph.set_edge_loss(ids[1], ids[2], 0.1).unwrap();
+        let router = Router::from_table(graph.dijkstra_route_table());
+
+        (graph, router)
+    }
+
+    #[test]
+    fn routes_use_the_loopback_address_as_source() {
+        let (graph, router) = line(["A", "B", "C"]);
+        let script = netns_script(&graph, &router, &NetnsOptions::default()).unwrap();
+
+        assert!(script.contains("ip netns add msp-A  # A"));
+        assert!(script.contains("ip link add l0a netns msp-A type veth peer name l0b netns msp-B"));
+        assert!(script.contains("tc -n msp-B qdisc add dev l1a root netem delay 3ms loss 10%"));
+        assert!(script.contains("ip -n msp-A route add 172.16.0.3/32 via 10.0.0.2 dev l0a src 172.16.0.1  # A -> C"));
+        assert!(script.contains("ip -n msp-C route add 172.16.0.1/32 via 10.0.0.5 dev l1b src 172.16.0.3  # C -> A"));
+    }
+
+    #[test]
+    fn hostile_ids_and_prefixes_cannot_inject_commands() {
+        let (graph, router) = line(["A", "B\nreboot; rm -rf /", "C"]);
+        let script = netns_script(&graph, &router, &NetnsOptions::default()).unwrap();
+
+        // Outside of comments the id only ever appears as its sanitized namespace name.
+        for line in script.lines() {
+            let command = line.split('#').next().unwrap();
+            assert!(!command.contains("reboot;") && !command.contains("rm -rf"), "{}", line);
+        }
+        assert!(script.contains("ip netns add msp-B_reboot__rm_-rf__  # B?reboot; rm -rf /"));
+
+        for prefix in ["x; reboot", "x\nreboot", "", "../x"] {
+            let options = NetnsOptions { prefix: Some(prefix.to_string()), ms_per_cost: None };
+            assert!(netns_script(&graph, &router, &options).is_err(), "{:?}", prefix);
+        }
+    }
+}
\ No newline at end of file
@@ -0,0 +1,263 @@
+use std::collections::{HashMap, HashSet};
+use chacha20poly1305::aead::{Aead, KeyInit, Payload};
+use chacha20poly1305::{ChaCha20Poly1305, Key, Nonce};
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+
+const NONCE_LEN: usize = 12;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct PacketHeader {
+    pub id: u64,
+    pub source: NodeId,
+    pub target: NodeId,
+}
+
+impl PacketHeader {
+    // Every layer is bound to the header, so a layer cannot be replayed inside another packet.
+    fn associated_data(&self) -> Vec<u8> {
+        let mut data = self.id.to_le_bytes().to_vec();
+        data.extend(self.source.as_bytes());
+        data.push(0xff);
+        data.extend(self.target.as_bytes());
+        data
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Packet {
+    pub header: PacketHeader,
+    pub payload: Vec<u8>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct HopObservation {
+    pub node: NodeId,
+    pub received: Vec<u8>,
+    pub next_hop: Option<NodeId>,
+    pub tampered: bool,
+}
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum DeliveryOutcome {
+    Delivered { payload: Vec<u8> },
+    Rejected { node: NodeId, reason: String },
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct EncryptedDelivery {
+    pub header: PacketHeader,
+    pub path: Vec<NodeId>,
+    pub hops: Vec<HopObservation>,
+    pub outcome: DeliveryOutcome,
+}
+
+// One ChaCha20-Poly1305 key per node. The sender wraps the payload once per hop, innermost layer
+// for the receiver, and every hop can only remove its own layer to learn the next hop.
+pub struct OnionSimulator {
+    keys: HashMap<NodeId, ChaCha20Poly1305>,
+    rng: StdRng,
+}
+
+impl OnionSimulator {
+    pub fn new(graph: &Graph, seed: Option<u64>) -> OnionSimulator {
+        let mut rng = match seed {
+            Some(seed) => StdRng::seed_from_u64(seed),
+            None => StdRng::from_entropy(),
+        };
+
+        let mut ids: Vec<&NodeId> = graph.nodes.keys().collect();
+        ids.sort();
+
+        let mut keys = HashMap::new();
+        for id in ids {
+            let mut key = [0u8; 32];
+            rng.fill_bytes(&mut key);
+            keys.insert(id.clone(), ChaCha20Poly1305::new(Key::from_slice(&key)));
+        }
+
+        OnionSimulator { keys, rng }
+    }
+
+    pub fn packet(&mut self, source: &str, target: &str, payload: Vec<u8>) -> Packet {
+        let header = PacketHeader { id: self.rng.next_u64(), source: source.to_string(), target: target.to_string() };
+        Packet { header, payload }
+    }
+
+    pub fn seal(&mut self, packet: &Packet, path: &[NodeId]) -> Result<Vec<u8>, String> {
+        if path.len() < 2 {
+            return Err("A path needs at least a sender and a receiver".to_string());
+        }
+
+        let aad = packet.header.associated_data();
+        let mut sealed = self.encrypt(&path[path.len() - 1], &packet.payload, &aad)?;
+
+        for index in (1..path.len() - 1).rev() {
+            let next_hop = path[index + 1].as_bytes();
+            let mut layer = (next_hop.len() as u16).to_be_bytes().to_vec();
+            layer.extend(next_hop);
+            layer.extend(sealed);
+
+            sealed = self.encrypt(&path[index], &layer, &aad)?;
+        }
+
+        Ok(sealed)
+    }
+
+    // Nodes in `tamper_at` flip a bit of whatever they forward; the next hop's authentication
+    // check is expected to reject it.
+    pub fn transmit(&mut self, packet: &Packet, path: &[NodeId], tamper_at: &HashSet<NodeId>) -> Result<EncryptedDelivery, String> {
+        let aad = packet.header.associated_data();
+        let mut sealed = self.seal(packet, path)?;
+        let mut hops = Vec::new();
+
+        let reject = |hops: Vec<HopObservation>, node: &NodeId, reason: String| EncryptedDelivery {
+            header: packet.header.clone(),
+            path: path.to_vec(),
+            hops,
+            outcome: DeliveryOutcome::Rejected { node: node.clone(), reason },
+        };
+
+        // Every relay peels its layer and forwards the rest; only the receiver's layer holds the payload.
+        for hop in path[1..].windows(2) {
+            let (node, expected) = (&hop[0], &hop[1]);
+            let received = sealed;
+
+            let opened = match self.decrypt(node, &received, &aad) {
+                Ok(opened) => opened,
+                Err(err) => {
+                    hops.push(HopObservation { node: node.clone(), received, next_hop: None, tampered: false });
+                    return Ok(reject(hops, node, err));
+                }
+            };
+
+            let (next_hop, mut inner) = match split_layer(&opened) {
+                Some(layer) => layer,
+                None => return Ok(reject(hops, node, "Malformed layer".to_string())),
+            };
+            if &next_hop != expected {
+                return Ok(reject(hops, node, format!("Layer points to {} instead of {}", next_hop, expected)));
+            }
+
+            let tampered = tamper_at.contains(node);
+            if tampered {
+                if let Some(byte) = inner.last_mut() {
+                    *byte ^= 0x01;
+                }
+            }
+
+            hops.push(HopObservation { node: node.clone(), received, next_hop: Some(next_hop), tampered });
+            sealed = inner;
+        }
+
+        let receiver = &path[path.len() - 1];
+        let outcome = match self.decrypt(receiver, &sealed, &aad) {
+            Ok(payload) => DeliveryOutcome::Delivered { payload },
+            Err(reason) => DeliveryOutcome::Rejected { node: receiver.clone(), reason },
+        };
+        hops.push(HopObservation { node: receiver.clone(), received: sealed, next_hop: None, tampered: false });
+
+        Ok(EncryptedDelivery { header: packet.header.clone(), path: path.to_vec(), hops, outcome })
+    }
+
+    fn encrypt(&mut self, node: &NodeId, plaintext: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let cipher = self.keys.get(node).ok_or(format!("Node {} has no key", node))?;
+
+        let mut nonce = [0u8; NONCE_LEN];
+        self.rng.fill_bytes(&mut nonce);
+        let ciphertext = cipher
+            .encrypt(Nonce::from_slice(&nonce), Payload { msg: plaintext, aad })
+            .map_err(|_| format!("Could not encrypt layer for {}", node))?;
+
+        let mut sealed = nonce.to_vec();
+        sealed.extend(ciphertext);
+        Ok(sealed)
+    }
+
+    fn decrypt(&self, node: &NodeId, sealed: &[u8], aad: &[u8]) -> Result<Vec<u8>, String> {
+        let cipher = self.keys.get(node).ok_or(format!("Node {} has no key", node))?;
+        if sealed.len() < NONCE_LEN {
+            return Err("Layer is truncated".to_string());
+        }
+
+        let (nonce, ciphertext) = sealed.split_at(NONCE_LEN);
+        cipher
+            .decrypt(Nonce::from_slice(nonce), Payload { msg: ciphertext, aad })
+            .map_err(|_| "Authentication failed".to_string())
+    }
+}
+
+fn split_layer(layer: &[u8]) -> Option<(NodeId, Vec<u8>)> {
+    let length = u16::from_be_bytes([*layer.first()?, *layer.get(1)?]) as usize;
+    let next_hop = String::from_utf8(layer.get(2..2 + length)?.to_vec()).ok()?;
+    Some((next_hop, layer[2 + length..].to_vec()))
+}
+
+#[cfg(test)]
+mod tests {
+    use std::collections::HashSet;
+    use crate::graph::graph::Graph;
+    use crate::graph::node::NodeId;
+    use super::{DeliveryOutcome, OnionSimulator};
+
+    const PAYLOAD: &[u8] = b"meet at the north gate";
+
+    fn line() -> (Graph, Vec<NodeId>) {
+        let mut graph = Graph::new();
+        let path: Vec<NodeId> = ["A", "B", "C", "D", "E"].iter().map(|id| id.to_string()).collect();
+        for hop in path.windows(2) {
+            graph.add_edge(hop[0].clone(), hop[1].clone(), 1).unwrap();
+        }
+
+        (graph, path)
+    }
+
+    fn contains(haystack: &[u8], needle: &[u8]) -> bool {
+        haystack.windows(needle.len()).any(|window| window == needle)
+    }
+
+    #[test]
+    fn intermediate_nodes_only_see_ciphertext() {
+        let (graph, path) = line();
+        let mut simulator = OnionSimulator::new(&graph, Some(7));
+        let packet = simulator.packet("A", "E", PAYLOAD.to_vec());
+
+        let delivery = simulator.transmit(&packet, &path, &HashSet::new()).unwrap();
+
+        assert_eq!(delivery.outcome, DeliveryOutcome::Delivered { payload: PAYLOAD.to_vec() });
+        assert_eq!(delivery.hops.len(), path.len() - 1);
+        for (index, hop) in delivery.hops.iter().enumerate() {
+            assert!(!contains(&hop.received, PAYLOAD), "{} saw the payload", hop.node);
+            if index + 1 < delivery.hops.len() {
+                assert_eq!(hop.next_hop.as_ref(), Some(&path[index + 2]));
+            }
+        }
+
+        let received: HashSet<&Vec<u8>> = delivery.hops.iter().map(|hop| &hop.received).collect();
+        assert_eq!(received.len(), delivery.hops.len());
+    }
+
+    #[test]
+    fn tampering_at_a_compromised_node_is_detected() {
+        let (graph, path) = line();
+        let mut simulator = OnionSimulator::new(&graph, Some(7));
+        let packet = simulator.packet("A", "E", PAYLOAD.to_vec());
+
+        let tamper_at: HashSet<NodeId> = ["D".to_string()].into_iter().collect();
+        let delivery = simulator.transmit(&packet, &path, &tamper_at).unwrap();
+
+        assert!(delivery.hops.iter().any(|hop| hop.node == "D" && hop.tampered));
+        match delivery.outcome {
+            DeliveryOutcome::Rejected { node, .. } => assert_eq!(node, "E"),
+            outcome => panic!("tampered packet was accepted: {:?}", outcome),
+        }
+
+        let tamper_at: HashSet<NodeId> = ["B".to_string()].into_iter().collect();
+        let delivery = simulator.transmit(&packet, &path, &tamper_at).unwrap();
+        assert!(matches!(delivery.outcome, DeliveryOutcome::Rejected { node, .. } if node == "C"));
+    }
+}
\ No newline at end of file
@@ -0,0 +1,160 @@
+use std::collections::HashMap;
+use std::io::{self, Write};
+use std::net::Ipv4Addr;
+use std::time::{SystemTime, UNIX_EPOCH};
+use serde::Serialize;
+use crate::graph::graph::Graph;
+use crate::graph::node::{Behavior, NodeId};
+use crate::utils::forwarding::{ForwardingReport, HopFate};
+
+// Raw IPv4 frames, so Wireshark dissects the IP and UDP headers without any extra setup.
+const LINKTYPE_RAW: u32 = 101;
+const SNAPLEN: u32 = 65535;
+const INITIAL_TTL: u8 = 64;
+const UDP_PORT: u16 = 9000;
+// Simulated gap between packets injected one after another.
+const INJECTION_INTERVAL_US: u64 = 1000;
+// Edge cost is read as milliseconds of link delay.
+const US_PER_COST: u64 = 1000;
+
+#[derive(Serialize)]
+struct HopRecord<'a> {
+    packet: u64,
+    attempt: usize,
+    hop: usize,
+    from: &'a NodeId,
+    to: &'a NodeId,
+    source: &'a NodeId,
+    target: &'a NodeId,
+    event: &'a str,
+}
+
+// Every link a simulated packet crosses becomes one IPv4/UDP frame. Addresses stand for the
+// packet's end-to-end source and target, the TTL drops by one per hop, the IP identification is
+// the packet sequence number and the UDP payload is a JSON record of the hop.
+pub fn write_pcap(graph: &Graph, report: &ForwardingReport, mut writer: impl Write) -> io::Result<usize> {
+    let mut ids: Vec<&NodeId> = graph.nodes.keys().collect();
+    ids.sort();
+    let addresses: HashMap<&NodeId, Ipv4Addr> = ids
+        .iter()
+        .enumerate()
+        .map(|(index, id)| (*id, Ipv4Addr::from(0x0a00_0000 + index as u32 + 1)))
+        .collect();
+    let unknown = Ipv4Addr::new(0, 0, 0, 0);
+
+    writer.write_all(&0xa1b2c3d4u32.to_le_bytes())?;
+    writer.write_all(&2u16.to_le_bytes())?;
+    writer.write_all(&4u16.to_le_bytes())?;
+    writer.write_all(&0i32.to_le_bytes())?;
+    writer.write_all(&0u32.to_le_bytes())?;
+    writer.write_all(&SNAPLEN.to_le_bytes())?;
+    writer.write_all(&LINKTYPE_RAW.to_le_bytes())?;
+
+    let start = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_micros() as u64);
+    let mut frames = Vec::new();
+
+    for (sequence, packet) in report.packets.iter().enumerate() {
+        let mut clock = start + sequence as u64 * INJECTION_INTERVAL_US;
+        let source = addresses.get(&packet.source).copied().unwrap_or(unknown);
+        let target = addresses.get(&packet.target).copied().unwrap_or(unknown);
+
+        for (attempt_index, attempt) in packet.attempts.iter().enumerate() {
+            let hops = attempt.path.len().saturating_sub(1);
+            for (hop_index, hop) in attempt.path.windows(2).enumerate() {
+                let event = match &attempt.fate {
+                    HopFate::Dropped { node } if node == &hop[1] => "dropped",
+                    HopFate::Delivered { .. } if hop_index + 1 == hops => "delivered",
+                    _ => "forwarded",
+                };
+
+                if hop_index > 0 {
+                    if let Some(Behavior::Delayer { delay }) = graph.nodes.get(&hop[0]).map(|node| &node.behavior) {
+                        clock += *delay as u64 * US_PER_COST;
+                    }
+                }
+                clock += graph.edges.get(&(hop[0].clone(), hop[1].clone())).map_or(0, |edge| edge.cost as u64) * US_PER_COST;
+
+                let record = HopRecord {
+                    packet: packet.id,
+                    attempt: attempt_index,
+                    hop: hop_index,
+                    from: &hop[0],
+                    to: &hop[1],
+                    source: &packet.source,
+                    target: &packet.target,
+                    event,
+                };
+                let payload = serde_json::to_vec(&record).map_err(|err| io::Error::new(io::ErrorKind::Other, err))?;
+                let ttl = INITIAL_TTL.saturating_sub(u8::try_from(hop_index).unwrap_or(u8::MAX));
+                frames.push((clock, ipv4_udp(source, target, ttl, packet.id as u16, &payload)?));
+
+                if event == "dropped" {
+                    break;
+                }
+            }
+        }
+    }
+
+    // Packets overlap in time, so frames are only written once they can be put in capture order.
+    frames.sort_by_key(|(clock, _)| *clock);
+    for (clock, frame) in &frames {
+        writer.write_all(&((clock / 1_000_000) as u32).to_le_bytes())?;
+        writer.write_all(&((clock % 1_000_000) as u32).to_le_bytes())?;
+        writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+        writer.write_all(&(frame.len() as u32).to_le_bytes())?;
+        writer.write_all(frame)?;
+    }
+
+    writer.flush()?;
+    Ok(frames.len())
+}
+
+fn ipv4_udp(source: Ipv4Addr, target: Ipv4Addr, ttl: u8, identification: u16, payload: &[u8]) -> io::Result<Vec<u8>> {
+    let total_length = u16::try_from(28 + payload.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidData, "Hop record does not fit in an IPv4 packet"))?;
+    let udp_length = total_length - 20;
+
+    let mut frame = Vec::with_capacity(total_length as usize);
+    frame.extend([0x45, 0x00]);
+    frame.extend(total_length.to_be_bytes());
+    frame.extend(identification.to_be_bytes());
+    frame.extend([0x40, 0x00, ttl, 17, 0x00, 0x00]);
+    frame.extend(source.octets());
+    frame.extend(target.octets());
+
+    let checksum = checksum(&frame);
+    frame[10..12].copy_from_slice(&checksum.to_be_bytes());
+
+    frame.extend(UDP_PORT.to_be_bytes());
+    frame.extend(UDP_PORT.to_be_bytes());
+    frame.extend(udp_length.to_be_bytes());
+    frame.extend([0x00, 0x00]);
+    frame.extend(payload);
+    Ok(frame)
+}
+
+fn checksum(header: &[u8]) -> u16 {
+    let mut sum: u32 = header.chunks(2).map(|word| u16::from_be_bytes([word[0], word[1]]) as u32).sum();
+    while sum > 0xffff {
+        sum = (sum & 0xffff) + (sum >> 16);
+    }
+
+    !(sum as u16)
+}
+
+#[cfg(test)]
+mod tests {
+    use std::net::Ipv4Addr;
+    use super::ipv4_udp;
+
+    #[test]
+    fn frames_carry_their_lengths_and_reject_oversized_records() {
+        let frame = ipv4_udp(Ipv4Addr::new(10, 0, 0, 1), Ipv4Addr::new(10, 0, 0, 2), 64, 7, b"hop").unwrap();
+        assert_eq!(frame.len(), 31);
+        assert_eq!(u16::from_be_bytes([frame[2], frame[3]]), 31);
+        assert_eq!(u16::from_be_bytes([frame[24], frame[25]]), 11);
+
+        assert!(ipv4_udp(Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST, 64, 7, &vec![0; 65_508]).is_err());
+        assert!(ipv4_udp(Ipv4Addr::LOCALHOST, Ipv4Addr::LOCALHOST, 64, 7, &vec![0; 65_507]).is_ok());
+    }
+}
\ No newline at end of file
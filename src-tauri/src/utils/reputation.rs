@@ -0,0 +1,184 @@
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
+use serde::Serialize;
+use crate::graph::node::NodeId;
+use crate::utils::forwarding::{ForwardingReport, HopFate};
+
+// Old evidence counts half as much after this long, so relays can earn their way back.
+const HALF_LIFE: Duration = Duration::from_secs(300);
+// A relay that drops everything makes its links this many times more expensive to route over.
+const PENALTY: f64 = 4.0;
+
+#[derive(Serialize, Clone, Debug, Default, PartialEq)]
+pub struct ReputationRecord {
+    pub confirmed: f64,
+    pub dropped: f64,
+    pub retried: f64,
+}
+
+impl ReputationRecord {
+    // Starts at 1.0 and only falls once drops are observed; one phantom confirmation keeps a single
+    // early drop from sinking the score to zero.
+    pub fn score(&self) -> f64 {
+        (self.confirmed + 1.0) / (self.confirmed + self.dropped + 1.0)
+    }
+
+    fn decay(&mut self, factor: f64) {
+        self.confirmed *= factor;
+        self.dropped *= factor;
+        self.retried *= factor;
+    }
+}
+
+#[derive(Clone, Debug, Default)]
+pub struct Reputation {
+    nodes: HashMap<NodeId, ReputationRecord>,
+    links: HashMap<(NodeId, NodeId), ReputationRecord>,
+    updated: Option<Instant>,
+}
+
+impl Reputation {
+    pub fn node_score(&self, id: &str) -> f64 {
+        self.nodes.get(id).map_or(1.0, ReputationRecord::score)
+    }
+
+    pub fn link_score(&self, source: &str, target: &str) -> f64 {
+        self.links.get(&(source.to_string(), target.to_string())).map_or(1.0, ReputationRecord::score)
+    }
+
+    pub fn node(&self, id: &str) -> Option<&ReputationRecord> {
+        self.nodes.get(id)
+    }
+
+    pub fn weight(&self, source: &NodeId, target: &NodeId, cost: u32) -> u32 {
+        let trust = self.node_score(target) * self.link_score(source, target);
+        (cost as f64 * (1.0 + PENALTY * (1.0 - trust))).ceil().min(u32::MAX as f64) as u32
+    }
+
+    pub fn observe(&mut self, report: &ForwardingReport) {
+        self.observe_at(report, Instant::now());
+    }
+
+    // Evidence is aged by the time elapsed since the previous observation before the new outcomes
+    // are counted.
+    pub fn observe_at(&mut self, report: &ForwardingReport, now: Instant) {
+        if let Some(updated) = self.updated {
+            let elapsed = now.saturating_duration_since(updated).as_secs_f64();
+            let factor = 0.5f64.powf(elapsed / HALF_LIFE.as_secs_f64());
+            for record in self.nodes.values_mut().chain(self.links.values_mut()) {
+                record.decay(factor);
+            }
+        }
+        self.updated = Some(now);
+
+        for packet in &report.packets {
+            for (number, attempt) in packet.attempts.iter().enumerate() {
+                let retried = number + 1 < packet.attempts.len();
+                let dropped_at = match &attempt.fate {
+                    HopFate::Dropped { node } => attempt.path.iter().position(|id| id == node),
+                    HopFate::Delivered { .. } => None,
+                };
+                let reached = dropped_at.unwrap_or(attempt.path.len() - 1);
+
+                for (index, hop) in attempt.path[..=reached].windows(2).enumerate() {
+                    let link = self.links.entry((hop[0].clone(), hop[1].clone())).or_default();
+                    let relay = self.nodes.entry(hop[1].clone()).or_default();
+
+                    if Some(index + 1) == dropped_at {
+                        link.dropped += 1.0;
+                        relay.dropped += 1.0;
+                        if retried {
+                            relay.retried += 1.0;
+                        }
+                    } else {
+                        link.confirmed += 1.0;
+                        relay.confirmed += 1.0;
+                    }
+                }
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use std::sync::Arc;
+    use std::time::Instant;
+    use crate::graph::graph::Graph;
+    use crate::graph::node::Behavior;
+    use crate::utils::forwarding::{simulate_forwarding, ForwardingConfig, ForwardingReport, TrafficSpec};
+    use crate::utils::router::Router;
+    use super::{Reputation, HALF_LIFE};
+
+    // Two equal-cost routes from A to D, one of them through a relay that drops everything. Flows
+    // are spread over both, so half of the four packets are dropped at B.
+    fn diamond() -> Graph {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph.add_edge("B".to_string(), "D".to_string(), 1).unwrap();
+        graph.add_edge("A".to_string(), "C".to_string(), 1).unwrap();
+        graph.add_edge("C".to_string(), "D".to_string(), 1).unwrap();
+        graph.set_node_behavior("B", Behavior::Blackhole).unwrap();
+        graph
+    }
+
+    fn report(graph: &Graph) -> ForwardingReport {
+        let router = Router::from_table(graph.dijkstra_route_table());
+        let config = ForwardingConfig {
+            traffic: vec![TrafficSpec { source: "A".to_string(), target: "D".to_string(), packets: 4 }],
+            retries: 0,
+            seed: Some(1),
+        };
+        simulate_forwarding(graph, &router, &config)
+    }
+
+    #[test]
+    fn drops_lower_the_scores_of_the_relay_and_its_link() {
+        let graph = diamond();
+        let mut reputation = Reputation::default();
+        reputation.observe(&report(&graph));
+
+        let relay = reputation.node("B").unwrap();
+        assert_eq!((relay.confirmed, relay.dropped), (0.0, 2.0));
+        assert!(reputation.node_score("B") < 0.5);
+        assert!(reputation.link_score("A", "B") < 0.5);
+        assert_eq!(reputation.node_score("C"), 1.0);
+        assert_eq!(reputation.link_score("A", "C"), 1.0);
+
+        assert_eq!(reputation.weight(&"A".to_string(), &"C".to_string(), 10), 10);
+        assert!(reputation.weight(&"A".to_string(), &"B".to_string(), 10) > 30);
+    }
+
+    #[test]
+    fn evidence_decays_with_time() {
+        let graph = diamond();
+        let mut reputation = Reputation::default();
+        let start = Instant::now();
+        reputation.observe_at(&report(&graph), start);
+        reputation.observe_at(&report(&graph), start);
+        assert_eq!(reputation.node("B").unwrap().dropped, 4.0);
+
+        reputation.observe_at(&ForwardingReport::default(), start + HALF_LIFE);
+        assert!((reputation.node("B").unwrap().dropped - 2.0).abs() < 1e-9);
+
+        reputation.observe_at(&ForwardingReport::default(), start + HALF_LIFE * 20);
+        assert!(reputation.node_score("B") > 0.99);
+    }
+
+    #[test]
+    fn equal_cost_paths_follow_the_weighted_table() {
+        let graph = diamond();
+        let router = Router::from_table(graph.dijkstra_route_table());
+        assert_eq!(router.get_equal_cost_paths("A", "D", 16, &graph).unwrap().paths.len(), 2);
+
+        let mut reputation = Reputation::default();
+        reputation.observe(&report(&graph));
+        let mut router = Router::from_table(graph.dijkstra_route_table_weighted(|from, to, cost| reputation.weight(from, to, cost), |_, _| true).unwrap());
+        router.reputation = Arc::new(reputation);
+
+        let ecmp = router.get_equal_cost_paths("A", "D", 16, &graph).unwrap();
+        assert_eq!(ecmp.paths, vec![vec!["A", "C", "D"]]);
+        assert_eq!(ecmp.cost, 2);
+        assert_eq!(router.route_packet_flow(&"A".to_string(), &"D".to_string(), 7, &graph, &router.neighbors(&graph)).unwrap(), vec!["A", "C", "D"]);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,92 @@
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::sync::mpsc::{channel, Receiver, RecvTimeoutError, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Duration;
+use serde::Serialize;
+use crate::graph::event::GraphChange;
+use crate::state::AppState;
+
+pub const ROUTES_PROGRESS_EVENT: &str = "routes-progress";
+const PROGRESS_STEPS: usize = 20;
+
+#[derive(Serialize, Clone, Debug, PartialEq)]
+#[serde(tag = "status", rename_all = "snake_case")]
+pub enum RouteProgress {
+    Started { version: u64 },
+    Running { version: u64, completed: usize, total: usize },
+    Cancelled { version: u64 },
+    Completed { version: u64, routes: usize },
+}
+
+pub trait RouteListener: Send + Sync + 'static {
+    fn progress(&self, progress: RouteProgress);
+    fn changes(&self, changes: Vec<GraphChange>);
+}
+
+pub struct RouteWorker {
+    sender: Mutex<Sender<u64>>,
+}
+
+impl RouteWorker {
+    pub fn spawn(state: Arc<AppState>, debounce: Duration, listener: impl RouteListener) -> RouteWorker {
+        let (sender, receiver) = channel();
+        thread::spawn(move || run(state, receiver, debounce, listener));
+
+        RouteWorker { sender: Mutex::new(sender) }
+    }
+
+    pub fn request(&self, version: u64) {
+        let _ = self.sender.lock().unwrap().send(version);
+    }
+}
+
+fn run(state: Arc<AppState>, receiver: Receiver<u64>, debounce: Duration, listener: impl RouteListener) {
+    while receiver.recv().is_ok() {
+        loop {
+            match receiver.recv_timeout(debounce) {
+                Ok(_) => continue,
+                Err(RecvTimeoutError::Timeout) => break,
+                Err(RecvTimeoutError::Disconnected) => return,
+            }
+        }
+
+        let topology = state.read();
+        if !topology.routes_stale {
+            continue;
+        }
+
+        let version = topology.version;
+        listener.progress(RouteProgress::Started { version });
+
+        let reported = AtomicUsize::new(0);
+        let routes = topology.route_table_cancellable(|completed, total| {
+            if state.version() != version {
+                return false;
+            }
+
+            let step = completed * PROGRESS_STEPS / total.max(1);
+            if reported.fetch_max(step, Ordering::Relaxed) < step {
+                listener.progress(RouteProgress::Running { version, completed, total });
+            }
+            true
+        });
+
+        let routes = match routes {
+            Some(routes) => routes,
+            None => {
+                listener.progress(RouteProgress::Cancelled { version });
+                continue;
+            }
+        };
+
+        let count = routes.len();
+        match state.publish_routes(version, routes) {
+            Some(changes) => {
+                listener.changes(changes);
+                listener.progress(RouteProgress::Completed { version, routes: count });
+            }
+            None => listener.progress(RouteProgress::Cancelled { version }),
+        }
+    }
+}
\ No newline at end of file
@@ -1,28 +1,70 @@
-use std::collections::{HashMap, HashSet};
-use std::sync::{Arc, Mutex};
+use std::collections::HashSet;
+use std::sync::Arc;
 use log::{error, info};
-use tauri::State;
-use crate::graph::graph::Graph;
+use crate::graph::algorithm::{self, RoutingAlgorithm, DEFAULT_ALGORITHM};
+use crate::graph::ecmp::{EqualCostPaths, WeightedNeighbors, DEFAULT_ECMP_LIMIT};
+use crate::graph::graph::{Graph, RouteMap};
 use crate::graph::node::NodeId;
+use crate::graph::route_table::{RouteMemoryStats, RouteTable};
+use crate::utils::reputation::Reputation;
 
+// `algorithm` fills the table and finds detours around unavailable nodes. `reputation` is the one
+// the table's distances were weighted with.
+#[derive(Clone)]
 pub struct Router {
-    pub routes: HashMap<(NodeId, NodeId), (Vec<NodeId>, u32)>
+    pub table: Arc<RouteTable>,
+    pub algorithm: Arc<dyn RoutingAlgorithm>,
+    pub reputation: Arc<Reputation>,
 }
 
 impl Router {
     pub fn new() -> Router {
-        Router { routes: HashMap::new() }
+        Router::from_table(RouteTable::default())
     }
 
-    pub fn from(routes: HashMap<(NodeId, NodeId), (Vec<NodeId>, u32)>) -> Router {
-        Router { routes }
+    pub fn from(routes: RouteMap) -> Router {
+        Router::from_table(RouteTable::from_paths(routes))
     }
 
-    pub fn route_packet_v2(&self, source: &NodeId, target: &NodeId, state: State<Arc<Mutex<Graph>>>) -> Result<Vec<NodeId>, String> {
-        if let Some((initial_path, _cost)) = self.routes.get(&(source.clone(), target.clone())) {
-            let mut path = initial_path.clone();
+    pub fn from_table(table: RouteTable) -> Router {
+        Router { table: Arc::new(table), algorithm: algorithm::algorithm(DEFAULT_ALGORITHM).unwrap(), reputation: Arc::default() }
+    }
+
+    pub fn compute(graph: &Graph, algorithm: Arc<dyn RoutingAlgorithm>) -> Router {
+        let table = algorithm.all_pairs(graph, &|_, _, cost| cost, &|_, _| true).unwrap_or_default();
+        Router { table: Arc::new(table), algorithm, reputation: Arc::default() }
+    }
+
+    pub fn route_count(&self) -> usize {
+        self.table.len()
+    }
+
+    pub fn memory_usage(&self) -> RouteMemoryStats {
+        self.table.memory_usage()
+    }
+
+    pub fn route_packet_v2(&self, source: &NodeId, target: &NodeId, graph: &Graph) -> Result<Vec<NodeId>, String> {
+        self.forward_packet(source, target, self.table.path(source, target), graph)
+    }
+
+    // `neighbors` comes from `neighbors(graph)`; simulations sending many flows build it once.
+    pub fn route_packet_flow(&self, source: &NodeId, target: &NodeId, flow: u64, graph: &Graph, neighbors: &WeightedNeighbors) -> Result<Vec<NodeId>, String> {
+        self.forward_packet(source, target, self.get_flow_path(source, target, flow, graph, neighbors), graph)
+    }
+
+    fn forward_packet(&self, source: &NodeId, target: &NodeId, route: Option<(Vec<NodeId>, u32)>, graph: &Graph) -> Result<Vec<NodeId>, String> {
+        if let Some((initial_path, _cost)) = route {
+            let mut path = initial_path;
             let mut visited_nodes = HashSet::new();
-            let mut graph = state.lock().unwrap();
+
+            // The table can predate a link that has since gone away; walking it would cross a hop
+            // that no longer exists.
+            if path.windows(2).any(|hop| !graph.edges.contains_key(&(hop[0].clone(), hop[1].clone()))) {
+                match self.algorithm.single_pair(graph, source, target, &HashSet::new()) {
+                    Some((detour, _)) => path = detour,
+                    None => return Err(format!("No path from {} to {}", source, target)),
+                }
+            }
 
             let mut index = 0;
             while index < path.len() {
@@ -35,7 +77,7 @@ impl Router {
                             return Err(format!("Cannot route packet from {} to {}", source.clone(), target.clone()));
                         }
 
-                        if let Some((p, _)) = graph.dijkstra_predecessors(source.clone(), target.clone()) {
+                        if let Some((p, _)) = self.algorithm.single_pair(graph, source, target, &HashSet::new()) {
                             path = p;
                             info!("Path: {:?}", path)
                         }
@@ -57,11 +99,10 @@ impl Router {
         }
     }
 
-    pub fn route_packet(&self, source: &NodeId, target: &NodeId, state: State<Arc<Mutex<Graph>>>) -> Result<Vec<NodeId>, String> {
-        if let Some((initial_path, _cost)) = self.routes.get(&(source.clone(), target.clone())) {
-            let mut path = initial_path.clone();
+    pub fn route_packet(&self, source: &NodeId, target: &NodeId, graph: &Graph) -> Result<Vec<NodeId>, String> {
+        if let Some((initial_path, _cost)) = self.table.path(source, target) {
+            let mut path = initial_path;
             let mut visited_nodes = HashSet::new();
-            let graph = state.lock().unwrap();
 
             let mut index = 0;
             while index < path.len() {
@@ -79,7 +120,7 @@ impl Router {
                             let mut exclude_nodes = HashSet::new();
                             exclude_nodes.insert(node_id.clone());
 
-                            if let Some(new_subpath) = graph.dijkstra_re_path(previous_node, target, &exclude_nodes) {
+                            if let Some((new_subpath, _)) = self.algorithm.single_pair(graph, previous_node, target, &exclude_nodes) {
                                 path.truncate(index);
                                 path.extend(new_subpath);
                                 if let Some(first_occurrence) = path.iter().position(|node_id| node_id == source) {
@@ -114,10 +155,24 @@ impl Router {
     }
 
     pub fn get_shortest_path(&self, source: NodeId, target: NodeId) -> Option<(Vec<NodeId>, u32)> {
-        if let Some(path) = self.routes.get(&(source.clone(), target.clone())) {
-            return Option::from(path.clone())
-        }
+        self.table.path(&source, &target)
+    }
 
-        None
+    pub fn neighbors(&self, graph: &Graph) -> WeightedNeighbors {
+        WeightedNeighbors::new(graph, |from, to, cost| self.reputation.weight(from, to, cost))
+    }
+
+    pub fn get_equal_cost_paths(&self, source: &str, target: &str, limit: usize, graph: &Graph) -> Option<EqualCostPaths> {
+        self.table.equal_cost_paths(graph, &self.neighbors(graph), source, target, limit)
+    }
+
+    pub fn get_flow_path(&self, source: &str, target: &str, flow: u64, graph: &Graph, neighbors: &WeightedNeighbors) -> Option<(Vec<NodeId>, u32)> {
+        match self.table.equal_cost_paths(graph, neighbors, source, target, DEFAULT_ECMP_LIMIT) {
+            Some(ecmp) => Some((ecmp.select(flow).clone(), ecmp.cost)),
+            None => self.table.path(source, target).map(|(path, cost)| {
+                let cost = graph.path_cost(&path).unwrap_or(cost);
+                (path, cost)
+            }),
+        }
     }
 }
\ No newline at end of file
@@ -0,0 +1,328 @@
+use std::collections::{BTreeSet, HashMap};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::graph::algorithm;
+use crate::graph::edge::Edge;
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+use crate::utils::router::Router;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum ScenarioEvent {
+    NodeFailure { node: NodeId },
+    NodeRecovery { node: NodeId },
+    LinkFailure { source: NodeId, target: NodeId },
+    LinkRecovery { source: NodeId, target: NodeId },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+pub struct TimedEvent {
+    pub time: u32,
+    #[serde(flatten)]
+    pub event: ScenarioEvent,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct StochasticModel {
+    pub mtbf: f64,
+    pub mttr: f64,
+    pub seed: u64,
+    #[serde(default)]
+    pub include_links: bool,
+}
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct ScenarioConfig {
+    pub duration: u32,
+    pub step: u32,
+    #[serde(default)]
+    pub events: Vec<TimedEvent>,
+    pub stochastic: Option<StochasticModel>,
+    pub flows: Option<Vec<(NodeId, NodeId)>>,
+    // Defaults to Floyd–Warshall, which scenarios have always been routed with.
+    pub algorithm: Option<String>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct StepStats {
+    pub time: u32,
+    pub failed_nodes: Vec<NodeId>,
+    pub failed_links: Vec<(NodeId, NodeId)>,
+    pub attempted: usize,
+    pub delivered: usize,
+    pub delivery_ratio: f64,
+    pub mean_stretch: Option<f64>,
+    pub max_stretch: Option<f64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ScenarioReport {
+    pub events: Vec<TimedEvent>,
+    pub steps: Vec<StepStats>,
+    pub delivery_ratio: f64,
+    pub mean_stretch: Option<f64>,
+    pub max_stretch: Option<f64>,
+}
+
+pub struct ScenarioEngine {
+    graph: Graph,
+    router: Router,
+    config: ScenarioConfig,
+    events: Vec<TimedEvent>,
+    flows: Vec<(NodeId, NodeId)>,
+    failed_nodes: BTreeSet<NodeId>,
+    failed_links: HashMap<(NodeId, NodeId), Edge>,
+}
+
+impl ScenarioEngine {
+    pub fn new(graph: &Graph, config: ScenarioConfig) -> Result<ScenarioEngine, String> {
+        if config.step == 0 {
+            return Err("Scenario step must be greater than zero".to_string());
+        }
+
+        let mut events = config.events.clone();
+        if let Some(model) = &config.stochastic {
+            events.extend(generate_events(graph, model, config.duration)?);
+        }
+        events.sort_by_key(|event| event.time);
+
+        let flows = match &config.flows {
+            Some(flows) => flows.clone(),
+            None => {
+                let node_ids = graph.get_node_ids();
+                node_ids.iter()
+                    .flat_map(|source| node_ids.iter().map(move |target| (source.clone(), target.clone())))
+                    .filter(|(source, target)| source != target)
+                    .collect()
+            }
+        };
+
+        Ok(ScenarioEngine {
+            graph: graph.clone(),
+            router: Router::compute(graph, algorithm::algorithm(config.algorithm.as_deref().unwrap_or(algorithm::DEFAULT_ALGORITHM))?),
+            config,
+            events,
+            flows,
+            failed_nodes: BTreeSet::new(),
+            failed_links: HashMap::new(),
+        })
+    }
+
+    pub fn run(&mut self) -> Result<ScenarioReport, String> {
+        let mut steps = Vec::new();
+        let mut next_event = 0;
+        let mut time = 0;
+
+        while time <= self.config.duration {
+            while next_event < self.events.len() && self.events[next_event].time <= time {
+                let event = self.events[next_event].event.clone();
+                self.apply(&event)?;
+                next_event += 1;
+            }
+
+            steps.push(self.route_flows(time));
+            time += self.config.step;
+        }
+
+        let attempted: usize = steps.iter().map(|step| step.attempted).sum();
+        let delivered: usize = steps.iter().map(|step| step.delivered).sum();
+        let stretches: Vec<f64> = steps.iter().filter_map(|step| step.mean_stretch).collect();
+
+        Ok(ScenarioReport {
+            events: self.events.clone(),
+            delivery_ratio: ratio(delivered, attempted),
+            mean_stretch: mean(&stretches),
+            max_stretch: steps.iter().filter_map(|step| step.max_stretch).reduce(f64::max),
+            steps,
+        })
+    }
+
+    fn apply(&mut self, event: &ScenarioEvent) -> Result<(), String> {
+        match event {
+            ScenarioEvent::NodeFailure { node } => {
+                self.graph.set_node_availability(node, false)?;
+                self.failed_nodes.insert(node.clone());
+            }
+            ScenarioEvent::NodeRecovery { node } => {
+                self.graph.set_node_availability(node, true)?;
+                self.failed_nodes.remove(node);
+            }
+            ScenarioEvent::LinkFailure { source, target } => {
+                for key in [(source.clone(), target.clone()), (target.clone(), source.clone())] {
+                    if let Some(edge) = self.graph.edges.remove(&key) {
+                        self.failed_links.insert(key, edge);
+                    }
+                }
+            }
+            ScenarioEvent::LinkRecovery { source, target } => {
+                for key in [(source.clone(), target.clone()), (target.clone(), source.clone())] {
+                    if let Some(edge) = self.failed_links.remove(&key) {
+                        self.graph.edges.insert(key, edge);
+                    }
+                }
+            }
+        }
+
+        Ok(())
+    }
+
+    fn route_flows(&self, time: u32) -> StepStats {
+        let mut attempted = 0;
+        let mut delivered = 0;
+        let mut stretches = Vec::new();
+
+        for (source, target) in &self.flows {
+            if self.failed_nodes.contains(source) || self.failed_nodes.contains(target) {
+                continue;
+            }
+
+            let baseline_cost = match self.router.get_shortest_path(source.clone(), target.clone()) {
+                Some((_, cost)) => cost,
+                None => continue,
+            };
+            attempted += 1;
+
+            if let Ok(path) = self.router.route_packet_v2(source, target, &self.graph) {
+                if let Some(cost) = self.graph.path_cost(&path) {
+                    delivered += 1;
+                    if baseline_cost > 0 {
+                        stretches.push(cost as f64 / baseline_cost as f64);
+                    }
+                }
+            }
+        }
+
+        let mut failed_links: Vec<(NodeId, NodeId)> = self.failed_links
+            .keys()
+            .filter(|(source, target)| source < target)
+            .cloned()
+            .collect();
+        failed_links.sort();
+
+        StepStats {
+            time,
+            failed_nodes: self.failed_nodes.iter().cloned().collect(),
+            failed_links,
+            attempted,
+            delivered,
+            delivery_ratio: ratio(delivered, attempted),
+            mean_stretch: mean(&stretches),
+            max_stretch: stretches.iter().cloned().reduce(f64::max),
+        }
+    }
+}
+
+fn generate_events(graph: &Graph, model: &StochasticModel, duration: u32) -> Result<Vec<TimedEvent>, String> {
+    if model.mtbf <= 0.0 || model.mttr <= 0.0 {
+        return Err("MTBF and MTTR must be positive".to_string());
+    }
+
+    let mut rng = StdRng::seed_from_u64(model.seed);
+    let mut events = Vec::new();
+
+    for node in graph.get_node_ids() {
+        for (failure_time, recovery_time) in failure_windows(&mut rng, model, duration) {
+            events.push(TimedEvent { time: failure_time, event: ScenarioEvent::NodeFailure { node: node.clone() } });
+            events.push(TimedEvent { time: recovery_time, event: ScenarioEvent::NodeRecovery { node: node.clone() } });
+        }
+    }
+
+    if model.include_links {
+        let mut links: Vec<(NodeId, NodeId)> = graph.edges
+            .keys()
+            .filter(|(source, target)| source < target)
+            .cloned()
+            .collect();
+        links.sort();
+
+        for (source, target) in links {
+            for (failure_time, recovery_time) in failure_windows(&mut rng, model, duration) {
+                events.push(TimedEvent {
+                    time: failure_time,
+                    event: ScenarioEvent::LinkFailure { source: source.clone(), target: target.clone() },
+                });
+                events.push(TimedEvent {
+                    time: recovery_time,
+                    event: ScenarioEvent::LinkRecovery { source: source.clone(), target: target.clone() },
+                });
+            }
+        }
+    }
+
+    Ok(events)
+}
+
+fn failure_windows(rng: &mut StdRng, model: &StochasticModel, duration: u32) -> Vec<(u32, u32)> {
+    let mut windows = Vec::new();
+    let mut time = 0.0;
+
+    loop {
+        time += exponential(rng, model.mtbf);
+        if time > duration as f64 {
+            break;
+        }
+        let failure_time = time.ceil() as u32;
+
+        time += exponential(rng, model.mttr);
+        let recovery_time = (time.ceil() as u32).max(failure_time + 1);
+        windows.push((failure_time, recovery_time));
+        time = recovery_time as f64;
+    }
+
+    windows
+}
+
+fn exponential(rng: &mut StdRng, mean: f64) -> f64 {
+    -mean * (1.0 - rng.gen::<f64>()).ln()
+}
+
+fn ratio(numerator: usize, denominator: usize) -> f64 {
+    if denominator == 0 {
+        return 1.0;
+    }
+
+    numerator as f64 / denominator as f64
+}
+
+fn mean(values: &[f64]) -> Option<f64> {
+    if values.is_empty() {
+        return None;
+    }
+
+    Some(values.iter().sum::<f64>() / values.len() as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+    use super::{ScenarioConfig, ScenarioEngine, ScenarioEvent, TimedEvent};
+
+    #[test]
+    fn flows_over_a_failed_link_take_the_redundant_one() {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "B".to_string(), 1).unwrap();
+        graph.add_edge("B".to_string(), "C".to_string(), 1).unwrap();
+        graph.add_edge("A".to_string(), "C".to_string(), 5).unwrap();
+
+        let config = ScenarioConfig {
+            duration: 10,
+            step: 10,
+            events: vec![
+                TimedEvent { time: 10, event: ScenarioEvent::LinkFailure { source: "B".to_string(), target: "C".to_string() } },
+            ],
+            stochastic: None,
+            flows: Some(vec![("A".to_string(), "C".to_string())]),
+            algorithm: None,
+        };
+        let report = ScenarioEngine::new(&graph, config).unwrap().run().unwrap();
+
+        assert_eq!(report.steps.len(), 2);
+        assert_eq!(report.steps[0].mean_stretch, Some(1.0));
+        assert_eq!(report.steps[1].failed_links, vec![("B".to_string(), "C".to_string())]);
+        assert_eq!(report.steps[1].delivered, 1);
+        assert_eq!(report.steps[1].mean_stretch, Some(2.5));
+        assert_eq!(report.delivery_ratio, 1.0);
+    }
+}
\ No newline at end of file
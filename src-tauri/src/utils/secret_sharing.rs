@@ -0,0 +1,295 @@
+use std::collections::{BTreeMap, HashSet};
+use rand::rngs::StdRng;
+use rand::{Rng, RngCore, SeedableRng};
+use serde::{Deserialize, Serialize};
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+
+#[derive(Deserialize, Clone, Debug)]
+pub struct SecretSharingConfig {
+    pub shares: u8,
+    pub threshold: u8,
+    #[serde(default)]
+    pub loss: f64,
+    #[serde(default)]
+    pub adversaries: Vec<NodeId>,
+    pub seed: Option<u64>,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Share {
+    pub x: u8,
+    pub bytes: Vec<u8>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct ShareRoute {
+    pub share: u8,
+    pub path: Vec<NodeId>,
+    pub delivered: bool,
+    pub lost_at: Option<NodeId>,
+    pub captured_by: Vec<NodeId>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SecretSharingReport {
+    pub shares: u8,
+    pub threshold: u8,
+    pub paths: usize,
+    pub disjoint: bool,
+    pub routes: Vec<ShareRoute>,
+    pub delivered: usize,
+    pub reconstructed: bool,
+    pub compromised: Vec<NodeId>,
+    pub captured: usize,
+    // Assumes the compromised nodes collude: the shares captured by any of them are pooled.
+    pub leaked: bool,
+    // Nodes that could rebuild the message alone, from the shares they captured themselves.
+    pub leaking_nodes: Vec<NodeId>,
+}
+
+// Shares are spread over node-disjoint paths found by excluding the relays of every path already
+// chosen. When the topology runs out of disjoint paths the shares wrap around the ones found, and
+// the report shows which relays end up holding enough shares to rebuild the message.
+pub fn send_secret_shared(graph: &Graph, source: &NodeId, target: &NodeId, payload: &[u8], config: &SecretSharingConfig) -> Result<SecretSharingReport, String> {
+    if config.threshold == 0 || config.threshold > config.shares {
+        return Err("Threshold must be between 1 and the number of shares".to_string());
+    }
+    if !(0.0..=1.0).contains(&config.loss) {
+        return Err("Loss probability must be between 0 and 1".to_string());
+    }
+    if source == target {
+        return Err("Source and target must differ".to_string());
+    }
+
+    let paths = disjoint_paths(graph, source, target, config.shares as usize);
+    if paths.is_empty() {
+        return Err(format!("No path from {} to {}", source, target));
+    }
+
+    let mut rng = match config.seed {
+        Some(seed) => StdRng::seed_from_u64(seed),
+        None => StdRng::from_entropy(),
+    };
+
+    let mut relays: Vec<&NodeId> = graph.nodes.keys().filter(|id| *id != source && *id != target).collect();
+    relays.sort();
+    let compromised: Vec<NodeId> = relays
+        .into_iter()
+        .filter(|id| config.adversaries.contains(id) || rng.gen_bool(graph.nodes[*id].compromise))
+        .cloned()
+        .collect();
+
+    let shares = split(payload, config.threshold, config.shares, &mut rng);
+    let mut routes = Vec::new();
+    let mut received = Vec::new();
+    let mut holdings: BTreeMap<NodeId, usize> = BTreeMap::new();
+
+    for (index, share) in shares.iter().enumerate() {
+        let path = &paths[index % paths.len()];
+        let mut captured_by = Vec::new();
+        let mut lost_at = None;
+
+        for hop in &path[..path.len() - 1] {
+            if compromised.contains(hop) {
+                captured_by.push(hop.clone());
+                *holdings.entry(hop.clone()).or_insert(0) += 1;
+            }
+            if rng.gen_bool(config.loss) {
+                lost_at = Some(hop.clone());
+                break;
+            }
+        }
+
+        if lost_at.is_none() {
+            received.push(share.clone());
+        }
+        routes.push(ShareRoute { share: share.x, path: path.clone(), delivered: lost_at.is_none(), lost_at, captured_by });
+    }
+
+    let reconstructed = received.len() >= config.threshold as usize && combine(&received) == payload;
+    let captured = routes.iter().filter(|route| !route.captured_by.is_empty()).count();
+    let leaking_nodes = holdings
+        .into_iter()
+        .filter(|(_, count)| *count >= config.threshold as usize)
+        .map(|(id, _)| id)
+        .collect();
+
+    Ok(SecretSharingReport {
+        shares: config.shares,
+        threshold: config.threshold,
+        paths: paths.len(),
+        disjoint: paths.len() >= config.shares as usize,
+        delivered: received.len(),
+        routes,
+        reconstructed,
+        compromised,
+        captured,
+        leaked: captured >= config.threshold as usize,
+        leaking_nodes,
+    })
+}
+
+fn disjoint_paths(graph: &Graph, source: &NodeId, target: &NodeId, limit: usize) -> Vec<Vec<NodeId>> {
+    let mut paths: Vec<Vec<NodeId>> = Vec::new();
+    let mut exclude_nodes = HashSet::new();
+    let mut without_direct_link: Option<Graph> = None;
+
+    while paths.len() < limit {
+        let current = without_direct_link.as_ref().unwrap_or(graph);
+        let path = match current.dijkstra_re_path(source, target, &exclude_nodes) {
+            Some(path) => path,
+            None => break,
+        };
+
+        // A direct link has no relays to exclude, so the link itself is left out of later searches.
+        // The search treats links as undirected, so both directions go.
+        if path.len() == 2 {
+            let mut reduced = current.clone();
+            reduced.edges.remove(&(source.clone(), target.clone()));
+            reduced.edges.remove(&(target.clone(), source.clone()));
+            without_direct_link = Some(reduced);
+        }
+
+        exclude_nodes.extend(path[1..path.len() - 1].iter().cloned());
+        paths.push(path);
+    }
+
+    paths
+}
+
+// Byte-wise Shamir sharing over GF(2^8): every byte of the secret is the constant term of its own
+// random polynomial of degree `threshold - 1`, and share `x` holds the polynomials evaluated at `x`.
+pub fn split(secret: &[u8], threshold: u8, shares: u8, rng: &mut impl RngCore) -> Vec<Share> {
+    let mut result: Vec<Share> = (1..=shares).map(|x| Share { x, bytes: Vec::with_capacity(secret.len()) }).collect();
+    let mut coefficients = vec![0u8; threshold as usize];
+
+    for &byte in secret {
+        coefficients[0] = byte;
+        rng.fill_bytes(&mut coefficients[1..]);
+
+        for share in &mut result {
+            let value = coefficients.iter().rev().fold(0, |acc, &coefficient| gf_mul(acc, share.x) ^ coefficient);
+            share.bytes.push(value);
+        }
+    }
+
+    result
+}
+
+pub fn combine(shares: &[Share]) -> Vec<u8> {
+    let length = shares.iter().map(|share| share.bytes.len()).min().unwrap_or(0);
+
+    (0..length)
+        .map(|index| {
+            shares.iter().enumerate().fold(0, |secret, (i, share)| {
+                let basis = shares.iter().enumerate().filter(|(j, _)| *j != i).fold(1, |basis, (_, other)| {
+                    gf_mul(basis, gf_div(other.x, other.x ^ share.x))
+                });
+                secret ^ gf_mul(share.bytes[index], basis)
+            })
+        })
+        .collect()
+}
+
+// Powers of the generator 3 and their discrete logarithms, so that products and quotients become
+// additions and subtractions of exponents. `exp` is doubled to skip the reduction modulo 255.
+struct Tables {
+    exp: [u8; 510],
+    log: [u8; 256],
+}
+
+static TABLES: Tables = tables();
+
+const fn tables() -> Tables {
+    let mut exp = [0u8; 510];
+    let mut log = [0u8; 256];
+    let mut power: u8 = 1;
+    let mut i = 0;
+    while i < 255 {
+        exp[i] = power;
+        exp[i + 255] = power;
+        log[power as usize] = i as u8;
+        // power * 3 = power * 2 + power, reduced by the AES polynomial.
+        let doubled = if power & 0x80 != 0 { (power << 1) ^ 0x1b } else { power << 1 };
+        power ^= doubled;
+        i += 1;
+    }
+
+    Tables { exp, log }
+}
+
+fn gf_mul(a: u8, b: u8) -> u8 {
+    if a == 0 || b == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[a as usize] as usize + TABLES.log[b as usize] as usize]
+}
+
+// Share coordinates are distinct and non-zero, so `b` never is.
+fn gf_div(a: u8, b: u8) -> u8 {
+    if a == 0 {
+        return 0;
+    }
+    TABLES.exp[TABLES.log[a as usize] as usize + 255 - TABLES.log[b as usize] as usize]
+}
+
+#[cfg(test)]
+mod tests {
+    use rand::rngs::StdRng;
+    use rand::SeedableRng;
+    use crate::graph::graph::Graph;
+    use super::{combine, gf_div, gf_mul, send_secret_shared, split, SecretSharingConfig};
+
+    #[test]
+    fn any_threshold_shares_rebuild_the_secret() {
+        let secret = b"only the receiver should read this".to_vec();
+        let shares = split(&secret, 3, 5, &mut StdRng::seed_from_u64(3));
+
+        for a in 0..5 {
+            for b in a + 1..5 {
+                for c in b + 1..5 {
+                    let subset = vec![shares[a].clone(), shares[b].clone(), shares[c].clone()];
+                    assert_eq!(combine(&subset), secret);
+                }
+                assert_ne!(combine(&[shares[a].clone(), shares[b].clone()]), secret);
+            }
+        }
+    }
+
+    #[test]
+    fn field_arithmetic_matches_the_aes_field() {
+        assert_eq!(gf_mul(0x57, 0x83), 0xc1);
+        assert_eq!(gf_mul(0x53, 0xca), 0x01);
+        assert_eq!(gf_div(0x01, 0x53), 0xca);
+
+        for a in 0..=255u8 {
+            assert_eq!(gf_mul(a, 0), 0);
+            for b in 1..=255u8 {
+                assert_eq!(gf_div(gf_mul(a, b), b), a);
+            }
+        }
+    }
+
+    #[test]
+    fn shares_use_detours_next_to_a_direct_link() {
+        let mut graph = Graph::new();
+        graph.add_edge("A".to_string(), "D".to_string(), 1).unwrap();
+        graph.add_edge("A".to_string(), "B".to_string(), 2).unwrap();
+        graph.add_edge("B".to_string(), "D".to_string(), 2).unwrap();
+        graph.add_edge("A".to_string(), "C".to_string(), 3).unwrap();
+        graph.add_edge("C".to_string(), "D".to_string(), 3).unwrap();
+
+        let config = SecretSharingConfig { shares: 3, threshold: 2, loss: 0.0, adversaries: vec!["B".to_string()], seed: Some(3) };
+        let report = send_secret_shared(&graph, &"A".to_string(), &"D".to_string(), b"meet at noon", &config).unwrap();
+
+        assert_eq!(report.paths, 3);
+        assert!(report.disjoint);
+        let paths: Vec<Vec<&str>> = report.routes.iter().map(|route| route.path.iter().map(String::as_str).collect()).collect();
+        assert_eq!(paths, vec![vec!["A", "D"], vec!["A", "B", "D"], vec!["A", "C", "D"]]);
+        assert_eq!(report.delivered, 3);
+        assert!(report.reconstructed);
+        assert_eq!(report.captured, 1);
+        assert!(!report.leaked);
+    }
+}
\ No newline at end of file
@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+use serde::{Deserialize, Serialize};
+use crate::graph::graph::Graph;
+use crate::graph::node::NodeId;
+use crate::utils::router::Router;
+
+#[derive(Serialize, Deserialize, Clone, Debug)]
+pub struct Demand {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub volume: u32,
+    #[serde(default)]
+    pub flow: Option<u64>,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct LinkLoad {
+    pub source: NodeId,
+    pub target: NodeId,
+    pub load: u64,
+    pub capacity: Option<u32>,
+    pub utilization: Option<f64>,
+    pub overloaded: bool,
+}
+
+#[derive(Serialize, Clone, Debug)]
+pub struct FlowReport {
+    pub links: Vec<LinkLoad>,
+    pub overloaded: Vec<(NodeId, NodeId)>,
+    pub unrouted: Vec<Demand>,
+    pub max_utilization: Option<f64>,
+}
+
+// Demands are forwarded the way packets are, so they detour around failed nodes and links the route
+// table may still use. A demand that can only end on an unavailable node is unrouted.
+pub fn simulate_flows(graph: &Graph, router: &Router, demands: &[Demand]) -> FlowReport {
+    let mut loads: HashMap<(NodeId, NodeId), u64> = HashMap::new();
+    let mut unrouted = Vec::new();
+    let neighbors = router.neighbors(graph);
+
+    for demand in demands {
+        let route = match demand.flow {
+            Some(flow) => router.route_packet_flow(&demand.source, &demand.target, flow, graph, &neighbors),
+            None => router.route_packet_v2(&demand.source, &demand.target, graph),
+        };
+        let route = route.ok().filter(|path| {
+            graph.path_cost(path).is_some() && path.iter().all(|id| graph.is_node_available(id))
+        });
+
+        match route {
+            Some(path) => {
+                for hop in path.windows(2) {
+                    *loads.entry((hop[0].clone(), hop[1].clone())).or_insert(0) += demand.volume as u64;
+                }
+            }
+            None => unrouted.push(demand.clone()),
+        }
+    }
+
+    let mut links: Vec<LinkLoad> = graph.edges
+        .iter()
+        .map(|((source, target), edge)| {
+            let load = loads.get(&(source.clone(), target.clone())).cloned().unwrap_or(0);
+            let utilization = edge.capacity.map(|capacity| {
+                if capacity == 0 {
+                    if load == 0 { 0.0 } else { f64::INFINITY }
+                } else {
+                    load as f64 / capacity as f64
+                }
+            });
+
+            LinkLoad {
+                source: source.clone(),
+                target: target.clone(),
+                load,
+                capacity: edge.capacity,
+                utilization,
+                overloaded: utilization.map_or(false, |u| u > 1.0),
+            }
+        })
+        .collect();
+    links.sort_by(|a, b| (&a.source, &a.target).cmp(&(&b.source, &b.target)));
+
+    let overloaded = links
+        .iter()
+        .filter(|link| link.overloaded)
+        .map(|link| (link.source.clone(), link.target.clone()))
+        .collect();
+
+    let max_utilization = links.iter().filter_map(|link| link.utilization).reduce(f64::max);
+
+    FlowReport { links, overloaded, unrouted, max_utilization }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::graph::graph::Graph;
+    use crate::utils::router::Router;
+    use super::{simulate_flows, Demand};
+
+    fn demand(source: &str, target: &str, volume: u32) -> Demand {
+        Demand { source: source.to_string(), target: target.to_string(), volume, flow: None }
+    }
+
+    // A-B-C is the cheap route, A-D-C the expensive one.
+    fn graph() -> Graph {
+        let mut graph = Graph::new();
+        for (source, target, cost) in [("A", "B", 1), ("B", "C", 1), ("A", "D", 2), ("D", "C", 2)] {
+            graph.add_edge(source.to_string(), target.to_string(), cost).unwrap();
+        }
+        graph.set_edge_capacity("A", "B", Some(10)).unwrap();
+        graph.set_edge_capacity("B", "C", Some(4)).unwrap();
+        graph
+    }
+
+    #[test]
+    fn loads_add_up_along_each_route() {
+        let graph = graph();
+        let router = Router::from_table(graph.dijkstra_route_table());
+        let report = simulate_flows(&graph, &router, &[demand("A", "C", 3), demand("B", "C", 2), demand("C", "B", 1)]);
+
+        let load = |source: &str, target: &str| report.links.iter().find(|link| link.source == source && link.target == target).unwrap();
+        assert_eq!(load("A", "B").load, 3);
+        assert_eq!(load("A", "B").utilization, Some(0.3));
+        assert_eq!(load("B", "C").load, 5);
+        assert!(load("B", "C").overloaded);
+        assert_eq!(load("C", "B").load, 1);
+        assert_eq!(load("A", "D").load, 0);
+        assert_eq!(load("A", "D").utilization, None);
+        assert_eq!(report.overloaded, vec![("B".to_string(), "C".to_string())]);
+        assert_eq!(report.max_utilization, Some(1.25));
+        assert!(report.unrouted.is_empty());
+    }
+
+    #[test]
+    fn failed_nodes_and_removed_links_carry_no_load() {
+        let mut graph = graph();
+        let router = Router::from_table(graph.dijkstra_route_table());
+        graph.set_node_availability("B", false).unwrap();
+
+        let report = simulate_flows(&graph, &router, &[demand("A", "C", 3), demand("A", "B", 1)]);
+        assert!(report.links.iter().filter(|link| link.source == "B" || link.target == "B").all(|link| link.load == 0));
+        assert_eq!(report.links.iter().find(|link| link.source == "D" && link.target == "C").unwrap().load, 3);
+        assert_eq!(report.unrouted.len(), 1);
+
+        graph.set_node_availability("B", true).unwrap();
+        graph.remove_edge("B".to_string(), "C".to_string()).unwrap();
+        let report = simulate_flows(&graph, &router, &[demand("A", "C", 3)]);
+        assert_eq!(report.links.iter().find(|link| link.source == "A" && link.target == "B").unwrap().load, 0);
+        assert_eq!(report.max_utilization, Some(0.0));
+    }
+}
\ No newline at end of file
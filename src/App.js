@@ -6,6 +6,7 @@ import { Network } from "vis-network";
 import { DataSet } from "vis-data";
 
 const { invoke } = window.__TAURI__.tauri;
+const { save } = window.__TAURI__.dialog;
 
 const App = () => {
     const graphContainer = useRef(null);
@@ -314,6 +315,53 @@ const App = () => {
         }
     };
 
+    const exportPcap = async () => {
+        const source = prompt("Enter source node ID:");
+        const target = prompt("Enter target node ID:");
+        const packets = parseInt(prompt("Enter number of packets:", "10"), 10);
+
+        if (source && target && !isNaN(packets)) {
+            try {
+                const path = await save({
+                    defaultPath: "traffic.pcap",
+                    filters: [{ name: "Packet capture", extensions: ["pcap"] }],
+                });
+                if (!path) {
+                    return;
+                }
+
+                const frames = await invoke("export_pcap", {
+                    path,
+                    config: { traffic: [{ source, target, packets }], retries: 1 },
+                });
+                alert(`Exported ${frames} frames to ${path}`);
+            } catch (err) {
+                console.error("Failed to export pcap:", err);
+                alert("Failed to export pcap: " + err);
+            }
+        } else {
+            alert("Invalid input!");
+        }
+    };
+
+    const exportNetnsScript = async () => {
+        try {
+            const path = await save({
+                defaultPath: "topology.sh",
+                filters: [{ name: "Shell script", extensions: ["sh"] }],
+            });
+            if (!path) {
+                return;
+            }
+
+            await invoke("export_netns_script", { path });
+            alert(`Exported namespace script to ${path}`);
+        } catch (err) {
+            console.error("Failed to export namespace script:", err);
+            alert("Failed to export namespace script: " + err);
+        }
+    };
+
     const resetGraph = () => {
         if (edgesData) {
             edgesData.forEach((edge) => {
@@ -351,6 +399,8 @@ const App = () => {
                 <button onClick={removeEdge}>Remove Edge</button>
                 {/*<button onClick={findShortestPath}>Find Shortest Path</button>*/}
                 <button onClick={routePacket}>Route Packet</button>
+                <button onClick={exportPcap}>Export PCAP</button>
+                <button onClick={exportNetnsScript}>Export Netns Script</button>
                 <button onClick={resetGraph}>Reset Graph</button>
             </div>
         </div>